@@ -0,0 +1,97 @@
+use crate::utils::shell_quote;
+use crate::workspace::config::{comma_list, template_rustflags};
+use crate::{ItemLocation, Profile, Template, TemplateItemId, TomlValue, WizardOptions};
+
+/// Translates the template applied to the given profile into values of Cargo `--config` arguments
+/// (e.g. `profile.dist.lto=true`), so that it can be used without modifying any files.
+pub fn template_config_args(
+    profile: &Profile,
+    template: &Template,
+    options: &WizardOptions,
+) -> Vec<String> {
+    let registry = options.item_registry();
+    let profile_prefix = format!("profile.{}", profile.name());
+
+    let mut args = vec![];
+    if !profile.is_builtin() {
+        args.push(format!(
+            "{profile_prefix}.inherits={}",
+            TomlValue::string(template.inherits().name()).to_toml_value()
+        ));
+    }
+    for (id, value) in template.iter_items() {
+        match registry.location(id) {
+            Some(ItemLocation::Profile(key)) => {
+                args.push(format!("{profile_prefix}.{key}={}", value.to_toml_value()));
+            }
+            Some(ItemLocation::Config { table, key }) => {
+                args.push(format!("{table}.{key}={}", value.to_toml_value()));
+            }
+            Some(ItemLocation::ConfigList { table, key }) => {
+                // Values that are not lists are passed as they are, so that Cargo reports them
+                let list = comma_list(value).unwrap_or_else(|| value.to_toml_value());
+                args.push(format!("{table}.{key}={list}"));
+            }
+            // Rustflags are joined into a single argument below
+            Some(ItemLocation::Rustflag(_) | ItemLocation::ExtraRustflags) | None => {}
+        }
+        // Cargo.toml enables this unstable feature using `cargo-features`
+        if *id == TemplateItemId::CodegenBackend {
+            args.push("unstable.codegen-backend=true".to_string());
+        }
+    }
+
+    let rustflags = template_rustflags(template, registry);
+    if !rustflags.is_empty() {
+        args.push(format!(
+            "build.rustflags={}",
+            TomlValue::array(
+                rustflags
+                    .iter()
+                    .map(|flag| TomlValue::string(flag))
+                    .collect()
+            )
+            .to_toml_value()
+        ));
+    }
+    args
+}
+
+/// Formats a `cargo` invocation that runs the given command (e.g. `build`) with the profile and
+/// the `--config` arguments of the template.
+pub fn format_cargo_invocation(command: &str, profile: &Profile, config_args: &[String]) -> String {
+    let mut invocation = format!("cargo {command} --profile {}", profile.name());
+    for arg in config_args {
+        invocation.push_str(" --config ");
+        invocation.push_str(&shell_quote(arg));
+    }
+    invocation
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::{format_cargo_invocation, template_config_args};
+    use crate::template::TemplateBuilder;
+    use crate::{BuiltinProfile, Profile, TemplateItemId, TomlValue, WizardOptions};
+
+    #[test]
+    fn config_args() {
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(TemplateItemId::Lto, TomlValue::bool(true))
+            .item(TemplateItemId::Panic, TomlValue::string("abort"))
+            .item(
+                TemplateItemId::TargetCpuInstructionSet,
+                TomlValue::string("native"),
+            )
+            .item(
+                TemplateItemId::BuildStd,
+                TomlValue::string("std,panic_abort"),
+            )
+            .build();
+        let profile = Profile::Custom("dist".to_string());
+        let args = template_config_args(&profile, &template, &WizardOptions::default());
+        insta::assert_snapshot!(format_cargo_invocation("build", &profile, &args), @r###"
+        cargo build --profile dist --config 'profile.dist.inherits="release"' --config 'profile.dist.lto=true' --config 'profile.dist.panic="abort"' --config 'unstable.build-std=["std", "panic_abort"]' --config 'build.rustflags=["-Ctarget-cpu=native"]'
+        "###);
+    }
+}
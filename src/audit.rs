@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+
+use crate::{
+    CargoManifest, ItemRegistry, PredefinedTemplateKind, Profile, TemplateItemId, TomlValue,
+};
+use crate::{Template, WizardError, WizardOptions, WizardResult};
+
+/// LTO can only optimize across codegen units of a crate to a limited extent, so a larger number
+/// of codegen units combined with LTO is reported. The default number of codegen units of the
+/// `release` profile (16) is not reported.
+const MAX_LTO_CODEGEN_UNITS: i64 = 16;
+
+/// Result of auditing a single Cargo profile.
+#[derive(Debug)]
+pub struct ProfileAudit {
+    profile: Profile,
+    closest_template: Option<TemplateMatch>,
+    issues: Vec<AuditIssue>,
+}
+
+impl ProfileAudit {
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Predefined template that most closely matches the profile.
+    pub fn closest_template(&self) -> Option<&TemplateMatch> {
+        self.closest_template.as_ref()
+    }
+
+    /// Settings of the profile that are likely counterproductive.
+    pub fn issues(&self) -> &[AuditIssue] {
+        &self.issues
+    }
+
+    /// Changes suggested for the profile. Fixes of issues come first, followed by changes that
+    /// would make the profile match its closest template.
+    pub fn suggested_changes(&self) -> Vec<(&TemplateItemId, &TomlValue)> {
+        let mut changes: Vec<_> = self
+            .issues
+            .iter()
+            .filter_map(|issue| issue.suggestion())
+            .collect();
+        if let Some(template) = &self.closest_template {
+            for (id, value) in &template.differences {
+                if !changes.iter().any(|(existing, _)| *existing == id) {
+                    changes.push((id, value));
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Predefined template matched against a profile.
+#[derive(Debug)]
+pub struct TemplateMatch {
+    kind: PredefinedTemplateKind,
+    matching: usize,
+    total: usize,
+    /// Profile items whose values differ from the template, with the values of the template.
+    differences: Vec<(TemplateItemId, TomlValue)>,
+}
+
+impl TemplateMatch {
+    pub fn kind(&self) -> PredefinedTemplateKind {
+        self.kind
+    }
+
+    /// Number of profile items of the template that have the same value in the profile.
+    pub fn matching_items(&self) -> usize {
+        self.matching
+    }
+
+    /// Number of profile items of the template.
+    pub fn total_items(&self) -> usize {
+        self.total
+    }
+}
+
+/// A combination of profile settings that is likely counterproductive, or a profile that
+/// Cargo cannot use at all (e.g. because of an inheritance cycle).
+#[derive(Debug)]
+pub struct AuditIssue {
+    message: String,
+    suggestion: Option<(TemplateItemId, TomlValue)>,
+}
+
+impl AuditIssue {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Item change that would resolve the issue, if the issue can be resolved by changing a
+    /// single item.
+    pub fn suggestion(&self) -> Option<(&TemplateItemId, &TomlValue)> {
+        self.suggestion.as_ref().map(|(id, value)| (id, value))
+    }
+}
+
+/// Audits the built-in profiles and all custom profiles defined in the manifest.
+pub fn audit_profiles(
+    manifest: &CargoManifest,
+    options: &WizardOptions,
+) -> WizardResult<Vec<ProfileAudit>> {
+    let templates: Vec<_> = PredefinedTemplateKind::value_variants()
+        .iter()
+        .map(|kind| (*kind, kind.build_template(options)))
+        .collect();
+
+    let registry = options.item_registry();
+    let mut profiles = vec![Profile::dev(), Profile::release()];
+    profiles.extend(
+        manifest
+            .get_profiles()
+            .iter()
+            .map(|name| Profile::from_name(name))
+            .filter(|profile| !profile.is_builtin()),
+    );
+
+    profiles
+        .into_iter()
+        .map(|profile| {
+            let values = match manifest.resolve_profile_values(&profile, registry) {
+                Ok(values) => values,
+                // The profile cannot be resolved, but other profiles can still be audited
+                Err(
+                    error @ (WizardError::MissingInherits { .. }
+                    | WizardError::InheritanceCycle { .. }),
+                ) => {
+                    return Ok(ProfileAudit {
+                        closest_template: None,
+                        issues: vec![AuditIssue {
+                            message: error.to_string(),
+                            suggestion: None,
+                        }],
+                        profile,
+                    });
+                }
+                Err(error) => return Err(error),
+            };
+            // Templates are only compared with profiles based on the same built-in profile, to
+            // avoid e.g. suggesting to disable optimizations in a release profile.
+            let base = manifest.inheritance_chain(&profile)?.pop();
+            let templates: Vec<_> = templates
+                .iter()
+                .filter(|(_, template)| Some(template.inherits()) == base.as_ref())
+                .collect();
+            Ok(ProfileAudit {
+                closest_template: find_closest_template(&values, &templates, registry),
+                issues: find_issues(&values, registry),
+                profile,
+            })
+        })
+        .collect()
+}
+
+fn find_closest_template(
+    values: &HashMap<TemplateItemId, TomlValue>,
+    templates: &[&(PredefinedTemplateKind, Template)],
+    registry: &ItemRegistry,
+) -> Option<TemplateMatch> {
+    let mut closest: Option<TemplateMatch> = None;
+    for (kind, template) in templates.iter().copied() {
+        // Only profile items can be compared, config items are not stored in the profile
+        let items: Vec<_> = template
+            .iter_items()
+            .filter(|(id, _)| registry.profile_key(id).is_some())
+            .collect();
+        let differences: Vec<_> = items
+            .iter()
+            .filter(|(id, value)| values.get(id) != Some(*value))
+            .map(|(id, value)| ((*id).clone(), (*value).clone()))
+            .collect();
+        let candidate = TemplateMatch {
+            kind: *kind,
+            matching: items.len() - differences.len(),
+            total: items.len(),
+            differences,
+        };
+        if closest
+            .as_ref()
+            .is_none_or(|closest| match_ratio(&candidate) > match_ratio(closest))
+        {
+            closest = Some(candidate);
+        }
+    }
+    closest
+}
+
+fn match_ratio(template: &TemplateMatch) -> f64 {
+    if template.total == 0 {
+        0.0
+    } else {
+        template.matching as f64 / template.total as f64
+    }
+}
+
+/// Finds combinations of settings that work against each other.
+fn find_issues(
+    values: &HashMap<TemplateItemId, TomlValue>,
+    registry: &ItemRegistry,
+) -> Vec<AuditIssue> {
+    let get = |id: TemplateItemId| values.get(&id);
+    let format_item = |id: TemplateItemId| format_item(values, &id, registry);
+
+    let lto_enabled = matches!(
+        get(TemplateItemId::Lto),
+        Some(TomlValue::Bool(true)) | Some(TomlValue::String(_))
+    ) && get(TemplateItemId::Lto) != Some(&TomlValue::string("off"));
+    let optimized = match get(TemplateItemId::OptimizationLevel) {
+        Some(TomlValue::Int(level)) => *level >= 2,
+        Some(TomlValue::String(_)) => true,
+        _ => false,
+    };
+
+    let mut issues = vec![];
+    if lto_enabled
+        && let Some(TomlValue::Int(cgus)) = get(TemplateItemId::CodegenUnits)
+        && *cgus > MAX_LTO_CODEGEN_UNITS
+    {
+        issues.push(AuditIssue {
+            message: format!(
+                "{} is combined with `codegen-units = {cgus}`, which limits the optimizations that LTO can perform",
+                format_item(TemplateItemId::Lto)
+            ),
+            suggestion: Some((TemplateItemId::CodegenUnits, TomlValue::int(1))),
+        });
+    }
+    if lto_enabled && get(TemplateItemId::OptimizationLevel) == Some(&TomlValue::int(0)) {
+        issues.push(AuditIssue {
+            message: format!(
+                "{} is combined with `opt-level = 0`, so LTO only makes the build slower",
+                format_item(TemplateItemId::Lto)
+            ),
+            suggestion: Some((TemplateItemId::Lto, TomlValue::bool(false))),
+        });
+    }
+    if optimized && get(TemplateItemId::Incremental) == Some(&TomlValue::bool(true)) {
+        issues.push(AuditIssue {
+            message: format!(
+                "`incremental = true` is combined with {}, which limits the optimizations of the compiler",
+                format_item(TemplateItemId::OptimizationLevel)
+            ),
+            suggestion: Some((TemplateItemId::Incremental, TomlValue::bool(false))),
+        });
+    }
+    let has_debuginfo = !matches!(
+        get(TemplateItemId::DebugInfo),
+        None | Some(TomlValue::Bool(false)) | Some(TomlValue::Int(0))
+    );
+    let strips_debuginfo = matches!(get(TemplateItemId::Strip), Some(TomlValue::Bool(true)))
+        || matches!(
+            get(TemplateItemId::Strip),
+            Some(TomlValue::String(strip)) if strip == "debuginfo" || strip == "symbols"
+        );
+    if has_debuginfo && strips_debuginfo {
+        issues.push(AuditIssue {
+            message: format!(
+                "{} generates debuginfo that is then removed by {}",
+                format_item(TemplateItemId::DebugInfo),
+                format_item(TemplateItemId::Strip)
+            ),
+            suggestion: Some((TemplateItemId::DebugInfo, TomlValue::bool(false))),
+        });
+    }
+    issues
+}
+
+fn format_item(
+    values: &HashMap<TemplateItemId, TomlValue>,
+    id: &TemplateItemId,
+    registry: &ItemRegistry,
+) -> String {
+    let name = registry.profile_key(id).unwrap_or_default();
+    match values.get(id) {
+        Some(value) => format!("`{name} = {}`", value.to_toml_value()),
+        None => format!("`{name}`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audit::audit_profiles;
+    use crate::{CargoManifest, PredefinedTemplateKind, TemplateItemId, WizardOptions};
+
+    #[test]
+    fn find_counterproductive_settings() {
+        let manifest = create_manifest(
+            r#"
+[profile.dev]
+lto = true
+
+[profile.release]
+incremental = true
+debug = true
+strip = true
+"#,
+        );
+        let audits = audit_profiles(&manifest, &WizardOptions::default()).unwrap();
+        let messages: Vec<_> = audits
+            .iter()
+            .map(|audit| {
+                let issues: Vec<_> = audit.issues().iter().map(|i| i.message()).collect();
+                format!("{}: {}", audit.profile().name(), issues.join("; "))
+            })
+            .collect();
+        insta::assert_snapshot!(messages.join("\n"), @r###"
+        dev: `lto = true` is combined with `codegen-units = 256`, which limits the optimizations that LTO can perform; `lto = true` is combined with `opt-level = 0`, so LTO only makes the build slower
+        release: `incremental = true` is combined with `opt-level = 3`, which limits the optimizations of the compiler; `debug = true` generates debuginfo that is then removed by `strip = true`
+        "###);
+    }
+
+    #[test]
+    fn match_closest_template() {
+        let manifest = create_manifest(
+            r#"
+[profile.dist]
+inherits = "release"
+lto = true
+codegen-units = 1
+"#,
+        );
+        let audits = audit_profiles(&manifest, &WizardOptions::default()).unwrap();
+        let dist = &audits[2];
+        assert_eq!(dist.profile().name(), "dist");
+        assert!(dist.issues().is_empty());
+
+        let template = dist.closest_template().unwrap();
+        assert!(matches!(
+            template.kind(),
+            PredefinedTemplateKind::FastRuntime
+        ));
+        assert_eq!(template.total_items() - template.matching_items(), 1);
+        assert_eq!(
+            dist.suggested_changes()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>(),
+            vec![&TemplateItemId::Panic]
+        );
+    }
+
+    #[test]
+    fn report_unresolvable_profiles() {
+        let manifest = create_manifest(
+            r#"
+[profile.a]
+opt-level = 1
+
+[profile.b]
+inherits = "c"
+
+[profile.c]
+inherits = "b"
+"#,
+        );
+        let audits = audit_profiles(&manifest, &WizardOptions::default()).unwrap();
+        let messages: Vec<_> = audits
+            .iter()
+            .skip(2)
+            .map(|audit| {
+                assert!(audit.closest_template().is_none());
+                assert!(audit.suggested_changes().is_empty());
+                let issues: Vec<_> = audit.issues().iter().map(|i| i.message()).collect();
+                format!("{}: {}", audit.profile().name(), issues.join("; "))
+            })
+            .collect();
+        let messages = messages
+            .join("\n")
+            .replace(&manifest.path().display().to_string(), "<path>");
+        insta::assert_snapshot!(messages, @r###"
+        a: The profile.a table in <path> does not exist or it does not specify `inherits`
+        b: Profile inheritance cycle detected: b -> c -> b
+        c: Profile inheritance cycle detected: c -> b -> c
+        "###);
+    }
+
+    fn create_manifest(text: &str) -> CargoManifest {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        std::fs::write(&path, text).unwrap();
+        CargoManifest::from_path(&path).unwrap()
+    }
+}
@@ -0,0 +1,50 @@
+use cargo_wizard::{KnownCargoOptions, ProfileAudit};
+
+use crate::dialog::utils;
+
+/// Prints the audit of workspace profiles, using the descriptions of known Cargo options.
+pub fn print_profile_audits(options: &KnownCargoOptions, audits: &[ProfileAudit]) {
+    for (index, audit) in audits.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!(
+            "Profile {}",
+            utils::profile_style().apply_to(audit.profile().name())
+        );
+        if let Some(template) = audit.closest_template() {
+            println!(
+                "  Closest template: {} ({}/{} settings match)",
+                utils::template_style().apply_to(utils::template_name(template.kind())),
+                template.matching_items(),
+                template.total_items()
+            );
+        }
+        for issue in audit.issues() {
+            println!("  ⚠️  {}", issue.message());
+        }
+
+        let changes = audit.suggested_changes();
+        if changes.is_empty() {
+            if audit.issues().is_empty() {
+                println!("  ✅ No changes suggested");
+            }
+            continue;
+        }
+        println!("  Suggested changes:");
+        for (id, value) in changes {
+            let description = options
+                .get_metadata(id)
+                .get_possible_values()
+                .iter()
+                .find(|possible| possible.value() == value)
+                .map(|possible| format!(" ({})", possible.description()))
+                .unwrap_or_default();
+            println!(
+                "    - {}: `{}`{description}",
+                options.get_name(id),
+                value.to_toml_value()
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use cargo_wizard::WizardError;
 use inquire::InquireError;
 
 #[derive(thiserror::Error, Debug)]
@@ -19,3 +20,9 @@ impl From<InquireError> for DialogError {
         }
     }
 }
+
+impl From<WizardError> for DialogError {
+    fn from(value: WizardError) -> Self {
+        Self::Generic(value.into())
+    }
+}
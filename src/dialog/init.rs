@@ -0,0 +1,123 @@
+use cargo_wizard::{
+    CargoWorkspace, KnownCargoOptions, PredefinedTemplateKind, Profile, Template, TemplateItemId,
+    WizardOptions,
+};
+
+use crate::cli::CliConfig;
+use crate::dialog::prompts::confirm_diff::{ConfirmDiffPromptResponse, prompt_confirm_diff};
+use crate::dialog::prompts::init_questions::{InitAnswers, prompt_init_questions};
+use crate::dialog::prompts::pin_toolchain::prompt_pin_nightly_toolchain;
+use crate::dialog::utils::{command_style, profile_style};
+use crate::dialog::{
+    PromptResult, on_template_applied, print_detected_toolchain, requires_nightly,
+};
+
+/// Sets up the profiles of a new project based on a few high-level questions.
+pub fn run_init_dialog(
+    cli_config: CliConfig,
+    cargo_options: KnownCargoOptions,
+    options: WizardOptions,
+    workspace: CargoWorkspace,
+) -> PromptResult<()> {
+    print_detected_toolchain(&options);
+
+    let answers = prompt_init_questions(&cli_config, options.nightly_items_enabled())?;
+    let mut options = options;
+    if answers.nightly && !options.nightly_items_enabled() {
+        options = options.with_nightly_items();
+    }
+
+    let targets = init_targets(&answers, &options);
+    for (_, _, template) in &targets {
+        cargo_options.validate_template(template)?;
+    }
+
+    if targets
+        .iter()
+        .any(|(_, _, template)| requires_nightly(&cargo_options, template))
+        && !targets
+            .iter()
+            .all(|(_, _, template)| workspace.has_pinned_nightly_toolchain(template))
+        && prompt_pin_nightly_toolchain(&cli_config)?
+    {
+        options = options.with_pinned_nightly_toolchain();
+    }
+
+    let templates: Vec<_> = targets
+        .iter()
+        .map(|(_, profile, template)| (profile, template))
+        .collect();
+    match prompt_confirm_diff(&cli_config, workspace, &templates, &options)? {
+        ConfirmDiffPromptResponse::Accepted(workspace) => {
+            let rustflags_overrides = workspace.rustflags_overrides().to_vec();
+            workspace.write()?;
+            for (index, (kind, profile, template)) in targets.iter().enumerate() {
+                // Print the overridden rustflags only once, they are shared by all profiles
+                let overrides = if index + 1 == targets.len() {
+                    rustflags_overrides.as_slice()
+                } else {
+                    &[]
+                };
+                on_template_applied(
+                    &cargo_options,
+                    *kind,
+                    template,
+                    profile,
+                    &options,
+                    overrides,
+                );
+            }
+            if answers.profiling {
+                println!(
+                    "⚠️  Frame pointers ({}) are not forced in the {} profile, because rustflags are shared by all profiles. Use {} to force them in all profiles.",
+                    command_style().apply_to("-Cforce-frame-pointers=yes"),
+                    profile_style().apply_to("profiling"),
+                    command_style().apply_to("cargo wizard apply profiling profiling")
+                );
+            }
+        }
+        ConfirmDiffPromptResponse::Denied => {
+            println!("No changes were made");
+        }
+        ConfirmDiffPromptResponse::NoDiff => {
+            println!("Nothing to apply, the profiles already matched the templates");
+        }
+    }
+    Ok(())
+}
+
+/// Templates and profiles that should be configured based on the answers.
+fn init_targets(
+    answers: &InitAnswers,
+    options: &WizardOptions,
+) -> Vec<(PredefinedTemplateKind, Profile, Template)> {
+    let mut targets = vec![];
+
+    let kind = PredefinedTemplateKind::FastCompile;
+    targets.push((kind, Profile::dev(), kind.build_template(options)));
+
+    let kind = PredefinedTemplateKind::FastRuntime;
+    let mut release = kind.build_template(options);
+    if answers.portable_release {
+        // Binaries compiled with `-Ctarget-cpu=native` might not work on other machines.
+        // An existing target CPU chosen by the user is kept.
+        release.omit_item(&TemplateItemId::TargetCpuInstructionSet);
+    }
+    targets.push((kind, Profile::release(), release));
+
+    // Rustflags and config keys are shared by all profiles, so the additional custom profiles
+    // only modify profile keys, to avoid affecting `dev` and `release`.
+    if answers.min_size {
+        let kind = PredefinedTemplateKind::MinSize;
+        let mut template = kind.build_template(options);
+        template.retain_profile_items(options.item_registry());
+        targets.push((kind, Profile::Custom("min-size".to_string()), template));
+    }
+    if answers.profiling {
+        let kind = PredefinedTemplateKind::Profiling;
+        let mut template = kind.build_template(options);
+        template.retain_profile_items(options.item_registry());
+        targets.push((kind, Profile::Custom("profiling".to_string()), template));
+    }
+    targets
+}
@@ -1,21 +1,22 @@
-use anyhow::Context;
-
+pub use audit::print_profile_audits;
 use cargo_wizard::{
-    BuiltinProfile, PredefinedTemplateKind, Profile, Template, WizardOptions, parse_workspace,
-    resolve_manifest_path,
+    BuiltinProfile, CargoWorkspace, KnownCargoOptions, PredefinedTemplateKind, Profile,
+    RustflagsOverride, Template, TemplateItemId, TomlValue, WizardOptions,
 };
 pub use error::{DialogError, PromptResult};
+pub use init::run_init_dialog;
 pub use utils::profile_from_str;
 
 use crate::cli::CliConfig;
-pub use crate::dialog::known_options::KnownCargoOptions;
 use crate::dialog::prompts::confirm_diff::{ConfirmDiffPromptResponse, prompt_confirm_diff};
-use crate::dialog::prompts::customize_template::prompt_customize_template;
+use crate::dialog::prompts::customize_template::{ConfigFileChoice, prompt_customize_template};
+use crate::dialog::prompts::pin_toolchain::prompt_pin_nightly_toolchain;
 use crate::dialog::prompts::select_profile::prompt_select_profile;
 use crate::dialog::prompts::select_template::prompt_select_template;
 
+mod audit;
 mod error;
-mod known_options;
+mod init;
 mod prompts;
 mod utils;
 
@@ -23,28 +24,82 @@ pub fn run_root_dialog(
     cli_config: CliConfig,
     cargo_options: KnownCargoOptions,
     options: WizardOptions,
+    workspace: CargoWorkspace,
 ) -> PromptResult<()> {
-    let manifest_path = resolve_manifest_path().context("Cannot resolve Cargo.toml path")?;
-    let workspace = parse_workspace(&manifest_path)?;
+    print_detected_toolchain(&options);
 
-    let existing_profiles = workspace
+    let existing_profiles: Vec<Profile> = workspace
         .existing_profiles()
         .iter()
         .filter_map(|s| profile_from_str(s).ok())
         .collect();
-    let profile = prompt_select_profile(&cli_config, existing_profiles)?;
+    let profile = prompt_select_profile(&cli_config, existing_profiles.clone())?;
+
+    // Custom profiles can inherit from any other profile, as long as it doesn't create a cycle
+    let inherit_choices = match profile {
+        Profile::Builtin(_) => None,
+        Profile::Custom(_) => {
+            let mut choices = vec![Profile::dev(), Profile::release()];
+            choices.extend(existing_profiles.into_iter().filter(|p| !p.is_builtin()));
+            choices.retain(|parent| workspace.check_inheritance(&profile, parent).is_ok());
+            Some(choices)
+        }
+    };
+
+    let mut config_choice = ConfigFileChoice {
+        workspace_root: workspace.root().to_path_buf(),
+        locations: workspace.config_locations(),
+        selected: options
+            .config_path()
+            .unwrap_or(workspace.config_path())
+            .to_path_buf(),
+    };
 
     let template_kind = prompt_select_template(&cli_config)?;
     let mut template = template_kind.build_template(&options);
 
     loop {
-        template = prompt_customize_template(&cli_config, &cargo_options, template)?;
+        template = prompt_customize_template(
+            &cli_config,
+            &cargo_options,
+            template,
+            inherit_choices.as_deref(),
+            &mut config_choice,
+        )?;
+        if let Err(error) = cargo_options.validate_template(&template) {
+            utils::clear_line();
+            println!("⚠️  {error}");
+            continue;
+        }
+
+        let mut apply_options = options
+            .clone()
+            .with_config_path(config_choice.selected.clone());
+        if requires_nightly(&cargo_options, &template)
+            && !workspace.has_pinned_nightly_toolchain(&template)
+            && prompt_pin_nightly_toolchain(&cli_config)?
+        {
+            apply_options = apply_options.with_pinned_nightly_toolchain();
+        }
 
-        let diff_result = prompt_confirm_diff(&cli_config, workspace.clone(), &profile, &template)?;
+        let diff_result = prompt_confirm_diff(
+            &cli_config,
+            workspace.clone(),
+            &[(&profile, &template)],
+            &apply_options,
+        )?;
         match diff_result {
             ConfirmDiffPromptResponse::Accepted(workspace) => {
+                let rustflags_overrides = workspace.rustflags_overrides().to_vec();
                 workspace.write()?;
-                on_template_applied(&cargo_options, template_kind, &template, &profile);
+                on_template_applied(
+                    &cargo_options,
+                    template_kind,
+                    &template,
+                    &profile,
+                    &apply_options,
+                    &rustflags_overrides,
+                );
                 break;
             }
             ConfirmDiffPromptResponse::Denied => {}
@@ -58,34 +113,54 @@ pub fn run_root_dialog(
     Ok(())
 }
 
+/// Prints the toolchain that was detected for the workspace, if any.
+fn print_detected_toolchain(options: &WizardOptions) {
+    if let Some(toolchain) = options.toolchain() {
+        println!(
+            "Detected toolchain: {}",
+            utils::toolchain_style().apply_to(format!(
+                "{} {} ({}){}",
+                toolchain.channel_name(),
+                toolchain.version(),
+                toolchain.host(),
+                toolchain
+                    .toolchain_file()
+                    .and_then(|path| path.file_name())
+                    .map(|name| format!(" from {}", name.to_string_lossy()))
+                    .unwrap_or_default()
+            ))
+        );
+    }
+}
+
 pub fn on_template_applied(
     options: &KnownCargoOptions,
     template_kind: PredefinedTemplateKind,
     template: &Template,
     profile: &Profile,
+    wizard_options: &WizardOptions,
+    rustflags_overrides: &[RustflagsOverride],
 ) {
     utils::clear_line();
     println!(
         "✅ Template {} applied to profile {}.",
-        utils::template_style().apply_to(match template_kind {
-            PredefinedTemplateKind::FastCompile => "FastCompile",
-            PredefinedTemplateKind::FastRuntime => "FastRuntime",
-            PredefinedTemplateKind::MinSize => "MinSize",
-        }),
+        utils::template_style().apply_to(utils::template_name(template_kind)),
         utils::profile_style().apply_to(profile.name())
     );
 
-    let requires_nightly = template
-        .iter_items()
-        .map(|(id, _)| id)
-        .any(|id| options.get_metadata(id).requires_nightly());
+    let requires_nightly = requires_nightly(options, template);
+    let pinned_nightly = requires_nightly && wizard_options.pin_nightly_toolchain_enabled();
     let profile_flag = match profile {
         Profile::Builtin(BuiltinProfile::Dev) => None,
         Profile::Builtin(BuiltinProfile::Release) => Some("--release".to_string()),
         Profile::Custom(profile) => Some(format!("--profile={profile}")),
     };
     if let Some(flag) = profile_flag {
-        let channel = if requires_nightly { "+nightly " } else { "" };
+        let channel = if requires_nightly && !pinned_nightly {
+            "+nightly "
+        } else {
+            ""
+        };
 
         println!(
             "⚠️  Do not forget to run `{}` to use the selected profile.",
@@ -94,12 +169,18 @@ pub fn on_template_applied(
     }
 
     for (id, value) in template.iter_items() {
-        if let Some(message) = options.get_metadata(id).on_applied(value) {
-            println!("{message}");
+        if let Some(warning) = options.get_metadata(id).on_applied(value) {
+            println!("{}", utils::format_item_warning(&warning));
         }
     }
+    utils::print_rustflags_overrides(rustflags_overrides);
 
-    if requires_nightly {
+    if pinned_nightly {
+        println!(
+            "✅ Nightly toolchain pinned in {}.",
+            utils::file_style().apply_to("rust-toolchain.toml")
+        );
+    } else if requires_nightly {
         println!("⚠️  You will have to use a nightly compiler.");
     }
 
@@ -119,15 +200,57 @@ pub fn on_template_applied(
             );
         }
         PredefinedTemplateKind::MinSize => {}
+        PredefinedTemplateKind::Profiling => {
+            if let Some(TomlValue::Bool(true)) =
+                template.get_item(&TemplateItemId::ForceFramePointers)
+            {
+                println!(
+                    "⚠️  Frame pointers ({}) are forced in all profiles, because rustflags are shared by all profiles.",
+                    utils::command_style().apply_to("-Cforce-frame-pointers=yes")
+                );
+            }
+            let target_dir = match profile {
+                Profile::Builtin(BuiltinProfile::Dev) => "debug",
+                profile => profile.name(),
+            };
+            let binary = format!("target/{target_dir}/<binary>");
+            println!(
+                "Tip: record a profile using {} and open it in the Firefox Profiler.",
+                utils::command_style().apply_to(format!("samply record {binary}"))
+            );
+            if cfg!(target_os = "linux") {
+                // Without frame pointers, perf has to unwind call stacks using debuginfo
+                let call_graph = match template.get_item(&TemplateItemId::ForceFramePointers) {
+                    Some(TomlValue::Bool(true)) => "fp",
+                    _ => "dwarf",
+                };
+                println!(
+                    "Tip: record a profile with perf using {}.",
+                    utils::command_style()
+                        .apply_to(format!("perf record --call-graph {call_graph} {binary}"))
+                );
+            }
+        }
     }
     let info_url = match template_kind {
         PredefinedTemplateKind::FastRuntime | PredefinedTemplateKind::FastCompile => {
             "https://nnethercote.github.io/perf-book/build-configuration.html"
         }
         PredefinedTemplateKind::MinSize => "https://github.com/johnthagen/min-sized-rust",
+        PredefinedTemplateKind::Profiling => {
+            "https://nnethercote.github.io/perf-book/profiling.html"
+        }
     };
     println!(
         "Tip: find more information at {}.",
         utils::command_style().apply_to(info_url)
     );
 }
+
+/// Checks if the template contains any items that require a nightly compiler.
+fn requires_nightly(options: &KnownCargoOptions, template: &Template) -> bool {
+    template
+        .iter_items()
+        .map(|(id, _)| id)
+        .any(|id| options.get_metadata(id).requires_nightly())
+}
@@ -5,11 +5,17 @@ use inquire::Confirm;
 use inquire::ui::{Color, RenderConfig};
 use similar::ChangeTag;
 
-use cargo_wizard::{CargoWorkspace, ModificationResult, ModifiedWorkspace, Profile, Template};
+use cargo_wizard::{
+    CargoWorkspace, ModificationResult, ModifiedWorkspace, Profile, Template, ToolchainFile,
+    WizardOptions,
+};
 
 use crate::cli::CliConfig;
 use crate::dialog::PromptResult;
-use crate::dialog::utils::{clear_line, colorize_render_config, create_render_config, file_style};
+use crate::dialog::utils::{
+    clear_line, colorize_render_config, create_render_config, display_config_path, file_style,
+    print_rustflags_overrides,
+};
 
 #[allow(clippy::large_enum_variant)]
 pub enum ConfirmDiffPromptResponse {
@@ -21,47 +27,44 @@ pub enum ConfirmDiffPromptResponse {
 pub fn prompt_confirm_diff(
     cli_config: &CliConfig,
     workspace: CargoWorkspace,
-    profile: &Profile,
-    template: &Template,
+    templates: &[(&Profile, &Template)],
+    options: &WizardOptions,
 ) -> PromptResult<ConfirmDiffPromptResponse> {
-    let modified = workspace.apply_template(profile, template)?;
+    let workspace_root = workspace.root().to_path_buf();
+    let modified = workspace.apply_templates(templates, options)?;
 
-    // Cargo.toml
-    let manifest_diff = match &modified.manifest() {
-        ModificationResult::NoChange => None,
-        ModificationResult::Modified { old, new } => {
-            Some(render_diff(&old.get_text(), &new.get_text()))
+    let diffs = [
+        (
+            "Cargo.toml".to_string(),
+            file_diff(modified.manifest(), |m| m.get_text()),
+        ),
+        (
+            display_config_path(modified.config_path(), &workspace_root),
+            file_diff(modified.config(), |c| c.get_text()),
+        ),
+        (
+            toolchain_file_name(modified.toolchain_file()),
+            file_diff(modified.toolchain_file(), |t| t.get_text()),
+        ),
+    ];
+    let mut changed_files = 0;
+    for (name, diff) in diffs {
+        if let Some(diff) = diff {
+            clear_line();
+            println!("{}", file_style().apply_to(name));
+            println!("{diff}");
+            changed_files += 1;
         }
-    };
-    let manifest_changed = manifest_diff.is_some();
-    if let Some(diff) = manifest_diff {
-        clear_line();
-        println!("{}", file_style().apply_to("Cargo.toml"));
-        println!("{diff}");
     }
 
-    // .cargo/config.toml
-    let config_diff = match &modified.config() {
-        ModificationResult::NoChange => None,
-        ModificationResult::Modified { old, new } => {
-            Some(render_diff(&old.get_text(), &new.get_text()))
-        }
-    };
-    let config_changed = config_diff.is_some();
-    if let Some(diff) = config_diff {
-        clear_line();
-        println!("{}", file_style().apply_to(".cargo/config.toml"));
-        println!("{diff}");
-    }
-
-    if !manifest_changed && !config_changed {
+    if changed_files == 0 {
         return Ok(ConfirmDiffPromptResponse::NoDiff);
     }
+    print_rustflags_overrides(modified.rustflags_overrides());
 
-    let multiple_diffs = manifest_changed && config_changed;
     let answer = Confirm::new(&format!(
         "Do you want to apply the above diff{}?",
-        if multiple_diffs { "s" } else { "" }
+        if changed_files > 1 { "s" } else { "" }
     ))
     .with_default(true)
     .with_render_config(confirm_render_diff(cli_config))
@@ -73,6 +76,29 @@ pub fn prompt_confirm_diff(
     })
 }
 
+fn file_diff<T, F: Fn(&T) -> String>(
+    result: &ModificationResult<T>,
+    get_text: F,
+) -> Option<String> {
+    match result {
+        ModificationResult::NoChange => None,
+        ModificationResult::Modified { old, new } => {
+            Some(render_diff(&get_text(old), &get_text(new)))
+        }
+    }
+}
+
+fn toolchain_file_name(result: &ModificationResult<ToolchainFile>) -> String {
+    match result {
+        ModificationResult::Modified { new, .. } => new
+            .path()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string()),
+        ModificationResult::NoChange => None,
+    }
+    .unwrap_or_else(|| "rust-toolchain.toml".to_string())
+}
+
 // Taken from https://github.com/mitsuhiko/similar/blob/main/examples/terminal-inline.rs
 fn render_diff(original: &str, new: &str) -> String {
     use std::fmt::Write;
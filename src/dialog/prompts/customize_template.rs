@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 use std::str;
 use std::str::FromStr;
 
@@ -7,34 +8,69 @@ use inquire::ui::{Color, RenderConfig};
 use inquire::validator::{ErrorMessage, Validation};
 use inquire::{Autocomplete, CustomUserError, Select, Text};
 
-use cargo_wizard::{Template, TemplateItemId, TomlValue};
+use cargo_wizard::{
+    ConfigLocation, ConfigLocationKind, CustomPossibleValue, KnownCargoOptions, ListPossibleValue,
+    PossibleValue, Profile, SelectedPossibleValue, Template, TemplateItemId, TemplateItemMetadata,
+    TomlValue, TomlValueKind,
+};
 
 use crate::cli::CliConfig;
 use crate::dialog::PromptResult;
-use crate::dialog::known_options::{
-    CustomPossibleValue, KnownCargoOptions, PossibleValue, SelectedPossibleValue,
-    TemplateItemMedata, TomlValueKind,
-};
-use crate::dialog::utils::{colorize_render_config, create_render_config};
+use crate::dialog::utils::{colorize_render_config, create_render_config, display_config_path};
+
+/// Cargo config file that will be modified by the template.
+pub struct ConfigFileChoice {
+    pub workspace_root: PathBuf,
+    /// Config files that can be selected.
+    pub locations: Vec<ConfigLocation>,
+    pub selected: PathBuf,
+}
+
+impl ConfigFileChoice {
+    fn display_selected(&self) -> String {
+        display_config_path(&self.selected, &self.workspace_root)
+    }
+}
 
 /// Customize the properties of a template, by choosing or modifying selected items.
+/// If `inherit_choices` is passed, the profile that the template inherits from can also be
+/// modified.
 pub fn prompt_customize_template(
     cli_config: &CliConfig,
     options: &KnownCargoOptions,
     mut template: Template,
+    inherit_choices: Option<&[Profile]>,
+    config_choice: &mut ConfigFileChoice,
 ) -> PromptResult<Template> {
     loop {
-        match prompt_choose_item_or_confirm_template(cli_config, options, &template)? {
+        match prompt_choose_item_or_confirm_template(
+            cli_config,
+            options,
+            &template,
+            inherit_choices.is_some(),
+            config_choice,
+        )? {
             ChooseItemResponse::ConfirmTemplate => {
                 break;
             }
+            ChooseItemResponse::ModifyConfigFile => {
+                if let Some(path) = prompt_select_config_file(cli_config, config_choice)? {
+                    config_choice.selected = path;
+                }
+            }
+            ChooseItemResponse::ModifyInherits => {
+                let choices = inherit_choices.unwrap_or_default();
+                if let Some(parent) = prompt_select_inherits(cli_config, &template, choices)? {
+                    template.set_inherits(parent);
+                }
+            }
             ChooseItemResponse::ModifyItem(id) => {
-                match prompt_select_value_for_item(cli_config, options, &template, id)? {
+                match prompt_select_value_for_item(cli_config, options, &template, &id)? {
                     SelectItemValueResponse::Set(value) => {
-                        template.insert_item(id.0, value);
+                        template.insert_item(id.id, value);
                     }
                     SelectItemValueResponse::Unset => {
-                        template.remove_item(id.0);
+                        template.remove_item(id.id);
                     }
                     SelectItemValueResponse::Cancel => {}
                 }
@@ -46,6 +82,8 @@ pub fn prompt_customize_template(
 
 enum ChooseItemResponse {
     ConfirmTemplate,
+    ModifyInherits,
+    ModifyConfigFile,
     ModifyItem(ItemId),
 }
 
@@ -55,12 +93,17 @@ fn prompt_choose_item_or_confirm_template(
     cli_config: &CliConfig,
     options: &KnownCargoOptions,
     template: &Template,
+    show_inherits: bool,
+    config_choice: &ConfigFileChoice,
 ) -> PromptResult<ChooseItemResponse> {
     enum Row<'a> {
         Confirm,
+        Inherits(&'a Profile),
+        ConfigFile(String),
         Item {
             id: ItemId,
-            metadata: TemplateItemMedata,
+            metadata: Box<TemplateItemMetadata>,
+            supported: bool,
             template: &'a Template,
         },
     }
@@ -69,9 +112,20 @@ fn prompt_choose_item_or_confirm_template(
         fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
             match self {
                 Row::Confirm => f.write_str("<Confirm>"),
+                Row::Inherits(profile) => {
+                    write!(f, "{:<30}", "Inherits from")?;
+                    let val = format!("[{}]", profile.name());
+                    write!(f, "{val:>10}")
+                }
+                Row::ConfigFile(path) => {
+                    write!(f, "{:<30}", "Config file")?;
+                    let val = format!("[{path}]");
+                    write!(f, "{val:>10}")
+                }
                 Row::Item {
                     id,
                     metadata,
+                    supported,
                     template,
                 } => {
                     let mut notes = vec![];
@@ -81,6 +135,9 @@ fn prompt_choose_item_or_confirm_template(
                     if metadata.requires_unix() {
                         notes.push("^");
                     }
+                    if !supported {
+                        notes.push("!");
+                    }
                     let name = format!(
                         "{id}{}",
                         if notes.is_empty() {
@@ -91,7 +148,7 @@ fn prompt_choose_item_or_confirm_template(
                     );
                     write!(f, "{name:<30}")?;
 
-                    if let Some(value) = template.get_item(id.0) {
+                    if let Some(value) = template.get_item(&id.id) {
                         let val = format!("[{}]", TomlValueDisplay(value));
                         write!(f, "{val:>10}")
                     } else {
@@ -103,59 +160,140 @@ fn prompt_choose_item_or_confirm_template(
     }
 
     let rows = std::iter::once(Row::Confirm)
-        .chain(
-            KnownCargoOptions::get_all_ids()
-                .iter()
-                .map(|&id| Row::Item {
-                    id: ItemId(id),
-                    metadata: options.get_metadata(id),
-                    template,
-                }),
-        )
+        .chain(show_inherits.then(|| Row::Inherits(template.inherits())))
+        .chain(std::iter::once(Row::ConfigFile(
+            config_choice.display_selected(),
+        )))
+        .chain(options.get_all_ids().into_iter().map(|id| {
+            let metadata = options.get_metadata(&id);
+            Row::Item {
+                id: ItemId::new(options, id),
+                supported: options.is_version_supported(metadata.min_version()),
+                metadata: Box::new(metadata),
+                template,
+            }
+        }))
         .collect();
     let answer = Select::new("Select items to modify or confirm the template:", rows)
         .with_page_size(12)
         .with_help_message(
-            "↑↓ to move, enter to select, type to filter. * Requires nightly compiler ^ Requires Unix",
+            "↑↓ to move, enter to select, type to filter. * Requires nightly compiler ^ Requires Unix ! Requires newer Cargo",
         )
         .with_render_config(customize_render_config(cli_config))
         .prompt()?;
     Ok(match answer {
         Row::Confirm => ChooseItemResponse::ConfirmTemplate,
+        Row::Inherits(_) => ChooseItemResponse::ModifyInherits,
+        Row::ConfigFile(_) => ChooseItemResponse::ModifyConfigFile,
         Row::Item { id, .. } => ChooseItemResponse::ModifyItem(id),
     })
 }
 
-#[derive(Copy, Clone)]
-struct ItemId(TemplateItemId);
+/// Select the profile that the template should inherit from.
+fn prompt_select_inherits(
+    cli_config: &CliConfig,
+    template: &Template,
+    choices: &[Profile],
+) -> PromptResult<Option<Profile>> {
+    struct Row(Profile);
+
+    impl Display for Row {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.0.name())
+        }
+    }
+
+    let index = choices
+        .iter()
+        .position(|p| p == template.inherits())
+        .unwrap_or_default();
+    let selected = Select::new(
+        "Select the profile to inherit from:",
+        choices.iter().cloned().map(Row).collect(),
+    )
+    .with_starting_cursor(index)
+    .with_help_message("↑↓ to move, enter to select, type to filter, ESC to cancel")
+    .with_render_config(customize_render_config(cli_config))
+    .prompt_skippable()?;
+    Ok(selected.map(|row| row.0))
+}
+
+/// Select the Cargo config file that should be modified by the template.
+fn prompt_select_config_file(
+    cli_config: &CliConfig,
+    config_choice: &ConfigFileChoice,
+) -> PromptResult<Option<PathBuf>> {
+    struct Row<'a> {
+        location: &'a ConfigLocation,
+        workspace_root: &'a Path,
+    }
+
+    impl Display for Row<'_> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let kind = match self.location.kind() {
+                ConfigLocationKind::Workspace => "Workspace",
+                ConfigLocationKind::ParentDirectory => "Parent directory",
+                ConfigLocationKind::CargoHome => "Cargo home",
+            };
+            write!(
+                f,
+                "{kind:<20}{}",
+                display_config_path(self.location.path(), self.workspace_root)
+            )?;
+            if !self.location.exists() {
+                f.write_str(" (new)")?;
+            }
+            Ok(())
+        }
+    }
+
+    let rows: Vec<_> = config_choice
+        .locations
+        .iter()
+        .map(|location| Row {
+            location,
+            workspace_root: &config_choice.workspace_root,
+        })
+        .collect();
+    let index = config_choice
+        .locations
+        .iter()
+        .position(|location| location.path() == config_choice.selected)
+        .unwrap_or_default();
+    let selected = Select::new("Select the config file that should be modified:", rows)
+        .with_starting_cursor(index)
+        .with_help_message("↑↓ to move, enter to select, type to filter, ESC to cancel")
+        .with_render_config(customize_render_config(cli_config))
+        .prompt_skippable()?;
+    Ok(selected.map(|row| row.location.path().to_path_buf()))
+}
+
+/// Template item along with the name that is shown in the dialog.
+pub struct ItemId {
+    id: TemplateItemId,
+    name: String,
+}
 
 impl ItemId {
-    fn value_set(&self, options: &KnownCargoOptions) -> TemplateItemMedata {
-        options.get_metadata(self.0)
+    fn new(options: &KnownCargoOptions, id: TemplateItemId) -> Self {
+        Self {
+            name: options.get_name(&id),
+            id,
+        }
+    }
+
+    fn value_set(&self, options: &KnownCargoOptions) -> TemplateItemMetadata {
+        options.get_metadata(&self.id)
     }
 
     fn selected_value(&self, template: &Template) -> Option<TomlValue> {
-        template.get_item(self.0).cloned()
+        template.get_item(&self.id).cloned()
     }
 }
 
 impl Display for ItemId {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let description = match self.0 {
-            TemplateItemId::DebugInfo => "Debug info",
-            TemplateItemId::Strip => "Strip symbols",
-            TemplateItemId::Lto => "Link-time optimizations",
-            TemplateItemId::CodegenUnits => "Number of codegen units (CGUs)",
-            TemplateItemId::Panic => "Panic handling mechanism",
-            TemplateItemId::OptimizationLevel => "Optimization level",
-            TemplateItemId::CodegenBackend => "Codegen backend",
-            TemplateItemId::TargetCpuInstructionSet => "Target CPU instruction set",
-            TemplateItemId::FrontendThreads => "Number of frontend threads",
-            TemplateItemId::Linker => "Linker",
-            TemplateItemId::Incremental => "Incremental compilation",
-            TemplateItemId::SplitDebugInfo => "Split debug info",
-        };
-        f.write_str(description)
+        f.write_str(&self.name)
     }
 }
 
@@ -167,6 +305,7 @@ impl<'a> Display for TomlValueDisplay<'a> {
             TomlValue::Int(value) => value.fmt(f),
             TomlValue::Bool(value) => value.fmt(f),
             TomlValue::String(value) => value.fmt(f),
+            TomlValue::Array(_) | TomlValue::Table(_) => self.0.to_toml_value().fmt(f),
         }
     }
 }
@@ -178,6 +317,8 @@ impl Display for ValueKindDisplay {
         let kind = match self.0 {
             TomlValueKind::Int => "int",
             TomlValueKind::String => "string",
+            TomlValueKind::Array => "array",
+            TomlValueKind::Table => "table",
         };
         f.write_str(kind)
     }
@@ -196,7 +337,7 @@ fn prompt_select_value_for_item(
     cli_config: &CliConfig,
     options: &KnownCargoOptions,
     template: &Template,
-    item_id: ItemId,
+    item_id: &ItemId,
 ) -> PromptResult<SelectItemValueResponse> {
     enum Row<'a> {
         ConstantValue(PossibleValue),
@@ -235,14 +376,25 @@ fn prompt_select_value_for_item(
     }
 
     let value_set = item_id.value_set(options);
+    if let Some(list_value) = value_set.get_list_value() {
+        return prompt_edit_list_value(cli_config, list_value, template, item_id);
+    }
     let selected_value = item_id.selected_value(template);
+
+    // Hide values that are not supported by the Cargo version of the workspace
+    let possible_values: Vec<_> = value_set
+        .get_possible_values()
+        .iter()
+        .filter(|value| {
+            options.is_version_supported(value.min_version().max(value_set.min_version()))
+        })
+        .cloned()
+        .collect();
     let selected_value = selected_value
-        .clone()
         .map(|v| value_set.get_selected_value(v))
         .unwrap_or(SelectedPossibleValue::None);
 
-    let mut rows: Vec<_> = value_set
-        .get_possible_values()
+    let mut rows: Vec<_> = possible_values
         .iter()
         .cloned()
         .map(Row::ConstantValue)
@@ -260,9 +412,16 @@ fn prompt_select_value_for_item(
     rows.push(Row::Cancel);
 
     let index = match selected_value {
-        SelectedPossibleValue::Constant { index, .. } => index,
+        // Select "Go back" as a default if the selected value is not supported
+        SelectedPossibleValue::Constant { index } => {
+            let selected = value_set.get_possible_values()[index].value();
+            possible_values
+                .iter()
+                .position(|v| v.value() == selected)
+                .unwrap_or(rows.len() - 1)
+        }
         // Select "Custom value" as a default if a custom value is selected
-        SelectedPossibleValue::Custom { .. } => value_set.get_possible_values().len(),
+        SelectedPossibleValue::Custom { .. } => possible_values.len(),
         // Select "Go back" as a default if no value is selected
         SelectedPossibleValue::None => rows.len() - 1,
     };
@@ -289,6 +448,127 @@ fn prompt_select_value_for_item(
     Ok(result)
 }
 
+/// Edit a list of entries (e.g. rustflags), by adding new entries or removing existing ones.
+fn prompt_edit_list_value(
+    cli_config: &CliConfig,
+    list_value: &ListPossibleValue,
+    template: &Template,
+    item_id: &ItemId,
+) -> PromptResult<SelectItemValueResponse> {
+    enum Row {
+        Entry(String),
+        Add,
+        Confirm,
+        Cancel,
+    }
+    impl Display for Row {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Row::Entry(entry) => f.write_str(entry),
+                Row::Add => f.write_str("<Add entry>"),
+                Row::Confirm => f.write_str("<Confirm>"),
+                Row::Cancel => f.write_str("<Go back>"),
+            }
+        }
+    }
+
+    let mut entries: Vec<String> = match item_id.selected_value(template) {
+        Some(TomlValue::String(value)) => value.split_whitespace().map(|s| s.to_string()).collect(),
+        Some(TomlValue::Array(values)) => values
+            .iter()
+            .filter_map(|value| match value {
+                TomlValue::String(value) => Some(value.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    loop {
+        let rows: Vec<_> = entries
+            .iter()
+            .cloned()
+            .map(Row::Entry)
+            .chain([Row::Add, Row::Confirm, Row::Cancel])
+            .collect();
+        let selected = Select::new(
+            &format!("Edit `{item_id}` (select an entry to remove it):"),
+            rows,
+        )
+        .with_starting_cursor(entries.len())
+        .with_help_message("↑↓ to move, enter to select, type to filter, ESC to cancel")
+        .with_render_config(customize_render_config(cli_config))
+        .prompt_skippable()?;
+
+        match selected {
+            Some(Row::Entry(entry)) => entries.retain(|e| e != &entry),
+            Some(Row::Add) => {
+                // Entries are not deduplicated, because some flags are split into an option and
+                // its value (e.g. `-C opt-level=1`)
+                entries.extend(prompt_enter_list_entry(cli_config, list_value)?);
+            }
+            Some(Row::Confirm) if entries.is_empty() => {
+                return Ok(SelectItemValueResponse::Unset);
+            }
+            Some(Row::Confirm) => {
+                return Ok(SelectItemValueResponse::Set(TomlValue::Array(
+                    entries.into_iter().map(TomlValue::String).collect(),
+                )));
+            }
+            Some(Row::Cancel) | None => return Ok(SelectItemValueResponse::Cancel),
+        }
+    }
+}
+
+/// Enter one or more whitespace-separated entries of a list, or a TOML array of entries.
+fn prompt_enter_list_entry(
+    cli_config: &CliConfig,
+    list_value: &ListPossibleValue,
+) -> PromptResult<Vec<String>> {
+    let value = Text::new("Enter entry: ")
+        .with_autocomplete(AutoCompleter(list_value.possible_entries().to_vec()))
+        .with_validator(|val: &str| {
+            if val.trim().is_empty() {
+                Ok(Validation::Invalid(ErrorMessage::Custom(
+                    "Value must not be empty".to_string(),
+                )))
+            } else if parse_list_entries(val).is_none() {
+                Ok(Validation::Invalid(ErrorMessage::Custom(
+                    "Invalid TOML array of strings".to_string(),
+                )))
+            } else {
+                Ok(Validation::Valid)
+            }
+        })
+        .with_render_config(customize_render_config(cli_config))
+        .with_help_message("↑↓ to select hint, tab to autocomplete hint, enter to submit")
+        .prompt()?;
+
+    // We expect that the entries have been parsed successfully thanks to the validator above
+    Ok(parse_list_entries(&value).expect("Could not parse entries"))
+}
+
+/// Splits entered list entries on whitespace. Entries that contain whitespace can be entered as a
+/// TOML array of strings.
+fn parse_list_entries(value: &str) -> Option<Vec<String>> {
+    let value = value.trim();
+    if value.starts_with('[') {
+        value
+            .parse::<toml_edit::Value>()
+            .ok()?
+            .as_array()?
+            .iter()
+            .map(|entry| entry.as_str().map(|entry| entry.to_string()))
+            .collect()
+    } else {
+        Some(
+            value
+                .split_whitespace()
+                .map(|entry| entry.to_string())
+                .collect(),
+        )
+    }
+}
+
 /// Enter a custom TOML value of the given kind.
 fn prompt_enter_custom_value(
     cli_config: &CliConfig,
@@ -311,6 +591,16 @@ fn prompt_enter_custom_value(
                 return Err("Value must not be empty");
             }
 
+            // Arrays and inline tables are parsed using TOML syntax
+            if s.starts_with('[') || s.starts_with('{') {
+                return s
+                    .parse::<toml_edit::Value>()
+                    .ok()
+                    .and_then(|value| TomlValue::from_toml_value(&value))
+                    .map(Self)
+                    .ok_or("Invalid TOML array or table");
+            }
+
             if let Ok(value) = bool::from_str(s) {
                 Ok(Self(TomlValue::Bool(value)))
             } else if let Ok(value) = i64::from_str(s) {
@@ -321,28 +611,6 @@ fn prompt_enter_custom_value(
         }
     }
 
-    #[derive(Clone)]
-    struct AutoCompleter(Vec<String>);
-
-    impl Autocomplete for AutoCompleter {
-        fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
-            Ok(self
-                .0
-                .clone()
-                .into_iter()
-                .filter(|v| v.contains(input))
-                .collect())
-        }
-
-        fn get_completion(
-            &mut self,
-            _input: &str,
-            highlighted_suggestion: Option<String>,
-        ) -> Result<Replacement, CustomUserError> {
-            Ok(highlighted_suggestion)
-        }
-    }
-
     // Ideally, we would use the CustomValue prompt here, but that doesn't support autocompletion.
     let kind = custom_value.kind();
     let value = Text::new(&format!(
@@ -355,20 +623,20 @@ fn prompt_enter_custom_value(
             Ok(val) => val,
             Err(error) => return Ok(Validation::Invalid(ErrorMessage::Custom(error.to_string()))),
         };
-        match kind {
-            TomlValueKind::Int if matches!(val.0, TomlValue::Int(_)) => Ok(Validation::Valid),
-            TomlValueKind::String if matches!(val.0, TomlValue::String(_)) => Ok(Validation::Valid),
-            TomlValueKind::Int | TomlValueKind::String => {
-                Ok(Validation::Invalid(ErrorMessage::Custom(format!(
-                    "Invalid TOML type, expected `{}`, got {}",
-                    ValueKindDisplay(kind),
-                    match val.0 {
-                        TomlValue::Int(_) => "int",
-                        TomlValue::Bool(_) => "bool",
-                        TomlValue::String(_) => "string",
-                    }
-                ))))
-            }
+        if kind.matches_value(&val.0) {
+            Ok(Validation::Valid)
+        } else {
+            Ok(Validation::Invalid(ErrorMessage::Custom(format!(
+                "Invalid TOML type, expected `{}`, got {}",
+                ValueKindDisplay(kind),
+                match val.0 {
+                    TomlValue::Int(_) => "int",
+                    TomlValue::Bool(_) => "bool",
+                    TomlValue::String(_) => "string",
+                    TomlValue::Array(_) => "array",
+                    TomlValue::Table(_) => "table",
+                }
+            ))))
         }
     })
     .with_render_config(customize_render_config(cli_config))
@@ -380,6 +648,29 @@ fn prompt_enter_custom_value(
     Ok(value.0)
 }
 
+/// Suggests entries that contain the current input.
+#[derive(Clone)]
+struct AutoCompleter(Vec<String>);
+
+impl Autocomplete for AutoCompleter {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+        Ok(self
+            .0
+            .clone()
+            .into_iter()
+            .filter(|v| v.contains(input))
+            .collect())
+    }
+
+    fn get_completion(
+        &mut self,
+        _input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, CustomUserError> {
+        Ok(highlighted_suggestion)
+    }
+}
+
 fn customize_render_config(cli_config: &CliConfig) -> RenderConfig<'static> {
     let render_config = create_render_config(cli_config);
     colorize_render_config(cli_config, render_config, Color::LightCyan)
@@ -0,0 +1,84 @@
+use std::fmt::{Display, Formatter};
+
+use inquire::ui::{Color, RenderConfig};
+use inquire::{Confirm, Select};
+
+use crate::cli::CliConfig;
+use crate::dialog::PromptResult;
+use crate::dialog::utils::{colorize_render_config, create_render_config};
+
+/// Answers to the high-level questions asked by `cargo wizard init`.
+pub struct InitAnswers {
+    /// Release builds are executed on other machines than the one where they were built.
+    pub portable_release: bool,
+    /// Create a custom profile optimized for binary size.
+    pub min_size: bool,
+    /// Create a custom profile for profiling optimized builds.
+    pub profiling: bool,
+    pub nightly: bool,
+}
+
+/// Asks the questions of `cargo wizard init`. The question about nightly is skipped if nightly
+/// items are already enabled (e.g. by a nightly toolchain or `--nightly=on`).
+pub fn prompt_init_questions(
+    cli_config: &CliConfig,
+    nightly_enabled: bool,
+) -> PromptResult<InitAnswers> {
+    enum BuildLocation {
+        Local,
+        Portable,
+    }
+
+    impl Display for BuildLocation {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self {
+                BuildLocation::Local => f.write_str("Locally, to run on this machine"),
+                BuildLocation::Portable => f.write_str(
+                    "In CI, or to run on other machines than the one where they are built",
+                ),
+            }
+        }
+    }
+
+    let render_config = init_render_config(cli_config);
+
+    let ships_binaries = Confirm::new("Is this a binary that you ship to users?")
+        .with_default(true)
+        .with_render_config(render_config)
+        .prompt()?;
+    let min_size = ships_binaries
+        && Confirm::new("Do you care about the size of the shipped binary?")
+            .with_help_message("A `min-size` profile will be created")
+            .with_default(false)
+            .with_render_config(render_config)
+            .prompt()?;
+    let location = Select::new(
+        "Where are your release builds made?",
+        vec![BuildLocation::Local, BuildLocation::Portable],
+    )
+    .with_render_config(render_config)
+    .prompt()?;
+    let profiling = Confirm::new("Do you want to profile optimized builds?")
+        .with_help_message("A `profiling` profile will be created")
+        .with_default(false)
+        .with_render_config(render_config)
+        .prompt()?;
+    let nightly = nightly_enabled
+        || Confirm::new("Do you use a nightly compiler?")
+            .with_help_message("Nightly-only configuration options will be used")
+            .with_default(false)
+            .with_render_config(render_config)
+            .prompt()?;
+
+    Ok(InitAnswers {
+        portable_release: matches!(location, BuildLocation::Portable),
+        min_size,
+        profiling,
+        nightly,
+    })
+}
+
+fn init_render_config(cli_config: &CliConfig) -> RenderConfig<'static> {
+    let render_config = create_render_config(cli_config);
+    colorize_render_config(cli_config, render_config, Color::DarkYellow)
+}
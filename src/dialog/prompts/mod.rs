@@ -1,4 +1,6 @@
 pub mod confirm_diff;
 pub mod customize_template;
+pub mod init_questions;
+pub mod pin_toolchain;
 pub mod select_profile;
 pub mod select_template;
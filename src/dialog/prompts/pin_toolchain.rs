@@ -0,0 +1,21 @@
+use inquire::Confirm;
+use inquire::ui::{Color, RenderConfig};
+
+use crate::cli::CliConfig;
+use crate::dialog::PromptResult;
+use crate::dialog::utils::{colorize_render_config, create_render_config};
+
+/// Ask if a nightly toolchain should be pinned in `rust-toolchain.toml`.
+pub fn prompt_pin_nightly_toolchain(cli_config: &CliConfig) -> PromptResult<bool> {
+    let answer = Confirm::new("Do you want to pin a nightly toolchain in rust-toolchain.toml?")
+        .with_help_message("Some of the selected items require a nightly compiler")
+        .with_default(false)
+        .with_render_config(pin_toolchain_render_config(cli_config))
+        .prompt()?;
+    Ok(answer)
+}
+
+fn pin_toolchain_render_config(cli_config: &CliConfig) -> RenderConfig<'static> {
+    let render_config = create_render_config(cli_config);
+    colorize_render_config(cli_config, render_config, Color::DarkMagenta)
+}
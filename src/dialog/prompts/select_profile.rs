@@ -8,7 +8,9 @@ use cargo_wizard::Profile;
 
 use crate::cli::CliConfig;
 use crate::dialog::PromptResult;
-use crate::dialog::utils::{colorize_render_config, create_render_config};
+use crate::dialog::utils::{
+    colorize_render_config, create_render_config, profile_from_str, validate_profile_name,
+};
 
 pub fn prompt_select_profile(
     cli_config: &CliConfig,
@@ -63,30 +65,15 @@ pub fn prompt_select_profile(
 fn prompt_enter_profile_name(cli_config: &CliConfig) -> PromptResult<Profile> {
     let profile = Text::new("Select profile name:")
         .with_validator(|input: &str| {
-            if input.is_empty() {
-                return Ok(Validation::Invalid(ErrorMessage::Custom(
-                    "Profile name must not be empty".to_string(),
-                )));
-            } else if !input
-                .chars()
-                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
-            {
-                // Yes, Cargo does allow any unicode alphabet char or digit :)
-                return Ok(Validation::Invalid(ErrorMessage::Custom(
-                    "Profile name may contain only letters, numbers, underscore and hyphen"
-                        .to_string(),
-                )));
-            }
-            Ok(Validation::Valid)
+            Ok(match validate_profile_name(input) {
+                Ok(()) => Validation::Valid,
+                Err(error) => Validation::Invalid(ErrorMessage::Custom(error)),
+            })
         })
         .with_render_config(profile_render_config(cli_config))
         .prompt()?;
-    let profile = match profile.as_str() {
-        "dev" => Profile::dev(),
-        "release" => Profile::release(),
-        _ => Profile::Custom(profile),
-    };
-    Ok(profile)
+    // The profile name has been validated above
+    Ok(profile_from_str(&profile).expect("Invalid profile name"))
 }
 
 fn profile_render_config(cli_config: &CliConfig) -> RenderConfig<'static> {
@@ -16,6 +16,9 @@ pub fn prompt_select_template(cli_config: &CliConfig) -> PromptResult<Predefined
                 PredefinedTemplateKind::FastCompile => "FastCompile: minimize compile times",
                 PredefinedTemplateKind::FastRuntime => "FastRuntime: maximize runtime performance",
                 PredefinedTemplateKind::MinSize => "MinSize: minimize binary size",
+                PredefinedTemplateKind::Profiling => {
+                    "Profiling: runtime performance with symbols for profilers"
+                }
             };
             f.write_str(msg)
         }
@@ -1,9 +1,9 @@
-use std::path::PathBuf;
+use std::path::Path;
 
 use console::Style;
 use inquire::ui::RenderConfig;
 
-use cargo_wizard::Profile;
+use cargo_wizard::{ItemWarning, PredefinedTemplateKind, Profile, RustflagsOverride};
 
 use crate::cli::CliConfig;
 
@@ -31,6 +31,15 @@ pub fn colorize_render_config<'a>(
     config
 }
 
+pub fn template_name(kind: PredefinedTemplateKind) -> &'static str {
+    match kind {
+        PredefinedTemplateKind::FastCompile => "FastCompile",
+        PredefinedTemplateKind::FastRuntime => "FastRuntime",
+        PredefinedTemplateKind::MinSize => "MinSize",
+        PredefinedTemplateKind::Profiling => "Profiling",
+    }
+}
+
 pub fn template_style() -> Style {
     Style::new().cyan()
 }
@@ -47,15 +56,45 @@ pub fn file_style() -> Style {
     Style::new().blue()
 }
 
+pub fn toolchain_style() -> Style {
+    Style::new().magenta()
+}
+
 /// Clear the current line to print arbitrary text after a prompt.
 pub fn clear_line() {
     print!("\r");
 }
 
-pub fn profile_from_str(text: &str) -> Result<Profile, String> {
-    if text.is_empty() {
-        return Err("Profile cannot be empty".to_string());
+/// Formats a warning about an applied template item, highlighting flags, commands and paths.
+pub fn format_item_warning(warning: &ItemWarning) -> String {
+    let message = warning.format_with(|text| command_style().apply_to(text).to_string());
+    format!("⚠️  {message}")
+}
+
+/// Displays the path of a config file relative to the workspace root, if it is located inside it.
+pub fn display_config_path(path: &Path, workspace_root: &Path) -> String {
+    path.strip_prefix(workspace_root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Warns that Cargo will ignore `build.rustflags`, because they are overridden by other sources.
+pub fn print_rustflags_overrides(overrides: &[RustflagsOverride]) {
+    if overrides.is_empty() {
+        return;
+    }
+    println!(
+        "⚠️  Cargo will ignore {}, because it is overridden by:",
+        file_style().apply_to("build.rustflags")
+    );
+    for item in overrides {
+        println!("    - {item}");
     }
+}
+
+pub fn profile_from_str(text: &str) -> Result<Profile, String> {
+    validate_profile_name(text)?;
     let profile = match text {
         "dev" => Profile::dev(),
         "release" => Profile::release(),
@@ -64,7 +103,66 @@ pub fn profile_from_str(text: &str) -> Result<Profile, String> {
     Ok(profile)
 }
 
-/// Quick check if a program with the given name can be found.
-pub fn find_program_path(name: &str) -> Option<PathBuf> {
-    which::which(name).ok()
+/// Checks that the profile name contains only valid characters and that it is not reserved by
+/// Cargo.
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Profile name must not be empty".to_string());
+    }
+    // Yes, Cargo does allow any unicode alphabet char or digit :)
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(
+            "Profile name may contain only letters, numbers, underscore and hyphen".to_string(),
+        );
+    }
+
+    // Names that would clash with profiles used internally by Cargo, along with the built-in
+    // profile that should be used instead.
+    let lower_name = name.to_lowercase();
+    let builtin_alias = match lower_name.as_str() {
+        "debug" => Some(("dev", "Cargo reserves it for the `dev` profile")),
+        "test" => Some(("dev", "`cargo test` uses it and it inherits from `dev`")),
+        "bench" => Some((
+            "release",
+            "`cargo bench` uses it and it inherits from `release`",
+        )),
+        "doc" => Some(("dev", "Cargo ignores it and `cargo doc` uses `dev`")),
+        _ => None,
+    };
+    if let Some((builtin, reason)) = builtin_alias {
+        return Err(format!(
+            "Profile name `{name}` is reserved, {reason}. Apply the template to `{builtin}` instead."
+        ));
+    }
+
+    // See https://github.com/rust-lang/cargo/blob/master/src/cargo/util/toml/mod.rs
+    let is_reserved = matches!(
+        lower_name.as_str(),
+        "build"
+            | "check"
+            | "clean"
+            | "config"
+            | "fetch"
+            | "fix"
+            | "install"
+            | "metadata"
+            | "package"
+            | "publish"
+            | "report"
+            | "root"
+            | "run"
+            | "rust"
+            | "rustc"
+            | "rustdoc"
+            | "target"
+            | "tmp"
+            | "uninstall"
+    ) || lower_name.starts_with("cargo");
+    if is_reserved {
+        return Err(format!("Profile name `{name}` is reserved by Cargo"));
+    }
+    Ok(())
 }
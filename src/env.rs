@@ -0,0 +1,260 @@
+use crate::utils::shell_quote;
+use crate::workspace::config::template_rustflags;
+use crate::{
+    ItemLocation, Profile, Template, TemplateItemId, TomlValue, WizardError, WizardOptions,
+    WizardResult,
+};
+
+/// Format of environment variables that configure Cargo instead of Cargo.toml and
+/// `.cargo/config.toml`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum EnvFormat {
+    /// `export` statements for POSIX shells.
+    Shell,
+    /// `.env` file.
+    Dotenv,
+    /// `env` section of a GitHub Actions workflow.
+    GithubActions,
+}
+
+/// Environment variable that configures Cargo.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnvVar {
+    name: String,
+    value: String,
+}
+
+impl EnvVar {
+    fn new(name: String, value: String) -> Self {
+        Self { name, value }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Translates the template applied to the given profile into environment variables
+/// (`CARGO_PROFILE_<NAME>_<KEY>`, `CARGO_BUILD_RUSTFLAGS`, etc.).
+///
+/// Items that were removed from the template cannot be expressed using environment variables, so
+/// they are ignored. Table values and rustflags that contain whitespace cannot be expressed either,
+/// so they result in an error.
+pub fn template_env_vars(
+    profile: &Profile,
+    template: &Template,
+    options: &WizardOptions,
+) -> WizardResult<Vec<EnvVar>> {
+    let registry = options.item_registry();
+    let profile_prefix = format!("CARGO_PROFILE_{}", env_key(profile.name()));
+
+    let mut vars = vec![];
+    if !profile.is_builtin() {
+        vars.push(EnvVar::new(
+            format!("{profile_prefix}_INHERITS"),
+            template.inherits().name().to_string(),
+        ));
+    }
+    for (id, value) in template.iter_items() {
+        let (setting, name, separator) = match registry.location(id) {
+            Some(ItemLocation::Profile(key)) => (
+                format!("profile.{}.{key}", profile.name()),
+                format!("{profile_prefix}_{}", env_key(key)),
+                " ",
+            ),
+            Some(ItemLocation::Config { table, key }) => (
+                format!("{table}.{key}"),
+                format!("CARGO_{}_{}", env_key(table), env_key(key)),
+                " ",
+            ),
+            Some(ItemLocation::ConfigList { table, key }) => (
+                format!("{table}.{key}"),
+                format!("CARGO_{}_{}", env_key(table), env_key(key)),
+                ",",
+            ),
+            // Rustflags are joined into a single variable below
+            Some(ItemLocation::Rustflag(_) | ItemLocation::ExtraRustflags) | None => continue,
+        };
+        let Some(value) = env_value(value, separator) else {
+            return Err(WizardError::UnsupportedEnvValue {
+                setting,
+                name,
+                reason: "tables cannot be expressed using environment variables",
+            });
+        };
+        vars.push(EnvVar::new(name, value));
+        // Cargo.toml enables this unstable feature using `cargo-features`
+        if *id == TemplateItemId::CodegenBackend {
+            vars.push(EnvVar::new(
+                "CARGO_UNSTABLE_CODEGEN_BACKEND".to_string(),
+                "true".to_string(),
+            ));
+        }
+    }
+
+    let rustflags = template_rustflags(template, registry);
+    if !rustflags.is_empty() {
+        // `CARGO_ENCODED_RUSTFLAGS` could express such flags, but it would also override
+        // `RUSTFLAGS` and `target.*.rustflags`, unlike `build.rustflags`
+        if let Some(flag) = rustflags
+            .iter()
+            .find(|flag| flag.contains(char::is_whitespace))
+        {
+            return Err(WizardError::UnsupportedEnvValue {
+                setting: format!("Rustflag `{flag}`"),
+                name: "CARGO_BUILD_RUSTFLAGS".to_string(),
+                reason: "Cargo splits its value on whitespace",
+            });
+        }
+        vars.push(EnvVar::new(
+            "CARGO_BUILD_RUSTFLAGS".to_string(),
+            rustflags.join(" "),
+        ));
+    }
+    Ok(vars)
+}
+
+/// Formats environment variables in the given format.
+pub fn format_env_vars(vars: &[EnvVar], format: EnvFormat) -> String {
+    let mut output = String::new();
+    if let EnvFormat::GithubActions = format {
+        output.push_str("env:\n");
+    }
+    for var in vars {
+        let line = match format {
+            EnvFormat::Shell => format!("export {}={}", var.name, shell_quote(&var.value)),
+            EnvFormat::Dotenv => format!("{}=\"{}\"", var.name, escape_double_quoted(&var.value)),
+            EnvFormat::GithubActions => {
+                format!("  {}: \"{}\"", var.name, escape_double_quoted(&var.value))
+            }
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+/// Converts a Cargo key (e.g. `opt-level`) into the form used in environment variables
+/// (`OPT_LEVEL`).
+fn env_key(key: &str) -> String {
+    key.to_uppercase().replace(['-', '.'], "_")
+}
+
+/// Tables cannot be expressed using environment variables. Entries of arrays are joined using the
+/// given separator.
+fn env_value(value: &TomlValue, separator: &str) -> Option<String> {
+    match value {
+        TomlValue::Int(value) => Some(value.to_string()),
+        TomlValue::Bool(value) => Some(value.to_string()),
+        TomlValue::String(value) => Some(value.clone()),
+        TomlValue::Array(values) => values
+            .iter()
+            .map(|value| env_value(value, separator))
+            .collect::<Option<Vec<_>>>()
+            .map(|values| values.join(separator)),
+        TomlValue::Table(_) => None,
+    }
+}
+
+fn escape_double_quoted(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', r#"\""#)
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use crate::env::{EnvFormat, format_env_vars, template_env_vars};
+    use crate::template::TemplateBuilder;
+    use crate::{BuiltinProfile, Profile, TemplateItemId, TomlValue, WizardOptions};
+
+    #[test]
+    fn profile_and_config_vars() {
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(TemplateItemId::OptimizationLevel, TomlValue::int(3))
+            .item(TemplateItemId::Lto, TomlValue::bool(true))
+            .item(
+                TemplateItemId::TargetCpuInstructionSet,
+                TomlValue::string("native"),
+            )
+            .item(
+                TemplateItemId::ExtraRustflags,
+                TomlValue::string("-Zshare-generics"),
+            )
+            .item(
+                TemplateItemId::BuildStd,
+                TomlValue::string("std,panic_abort"),
+            )
+            .build();
+        let vars = template_env_vars(
+            &Profile::Custom("dist-lto".to_string()),
+            &template,
+            &WizardOptions::default(),
+        )
+        .unwrap();
+        insta::assert_snapshot!(format_env_vars(&vars, EnvFormat::Shell), @r###"
+        export CARGO_PROFILE_DIST_LTO_INHERITS='release'
+        export CARGO_PROFILE_DIST_LTO_OPT_LEVEL='3'
+        export CARGO_PROFILE_DIST_LTO_LTO='true'
+        export CARGO_UNSTABLE_BUILD_STD='std,panic_abort'
+        export CARGO_BUILD_RUSTFLAGS='-Ctarget-cpu=native -Zshare-generics'
+        "###);
+    }
+
+    #[test]
+    fn env_formats() {
+        let template = TemplateBuilder::new(BuiltinProfile::Dev)
+            .item(TemplateItemId::Panic, TomlValue::string("abort"))
+            .item(
+                TemplateItemId::ExtraRustflags,
+                TomlValue::string("-Cfoo='a\"b'"),
+            )
+            .build();
+        let vars =
+            template_env_vars(&Profile::dev(), &template, &WizardOptions::default()).unwrap();
+        insta::assert_snapshot!(format_env_vars(&vars, EnvFormat::Shell), @r###"
+        export CARGO_PROFILE_DEV_PANIC='abort'
+        export CARGO_BUILD_RUSTFLAGS='-Cfoo='\''a"b'\'''
+        "###);
+        insta::assert_snapshot!(format_env_vars(&vars, EnvFormat::Dotenv), @r###"
+        CARGO_PROFILE_DEV_PANIC="abort"
+        CARGO_BUILD_RUSTFLAGS="-Cfoo='a\"b'"
+        "###);
+        insta::assert_snapshot!(format_env_vars(&vars, EnvFormat::GithubActions), @r###"
+        env:
+          CARGO_PROFILE_DEV_PANIC: "abort"
+          CARGO_BUILD_RUSTFLAGS: "-Cfoo='a\"b'"
+        "###);
+    }
+
+    #[test]
+    fn reject_values_that_cannot_be_expressed() {
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::ExtraRustflags,
+                TomlValue::array(vec![TomlValue::string("-Clink-arg=-Wl,--foo bar")]),
+            )
+            .build();
+        let error = template_env_vars(&Profile::release(), &template, &WizardOptions::default())
+            .unwrap_err();
+        insta::assert_snapshot!(error.to_string(), @r###"
+        Rustflag `-Clink-arg=-Wl,--foo bar` cannot be set using the CARGO_BUILD_RUSTFLAGS environment variable, because Cargo splits its value on whitespace
+        "###);
+
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::DebugInfo,
+                TomlValue::Table(IndexMap::from([("foo".to_string(), TomlValue::int(1))])),
+            )
+            .build();
+        let error = template_env_vars(&Profile::release(), &template, &WizardOptions::default())
+            .unwrap_err();
+        insta::assert_snapshot!(error.to_string(), @r###"
+        profile.release.debug cannot be set using the CARGO_PROFILE_RELEASE_DEBUG environment variable, because tables cannot be expressed using environment variables
+        "###);
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+/// Errors that can occur when loading, modifying or writing the files of a Cargo workspace.
+#[derive(thiserror::Error, Debug)]
+pub enum WizardError {
+    #[error("Cargo.toml manifest {} does not exist", path.display())]
+    ManifestNotFound { path: PathBuf },
+    #[error("Cannot get cargo metadata{}", format_metadata_path(path))]
+    Metadata {
+        path: Option<PathBuf>,
+        #[source]
+        source: cargo_metadata::Error,
+    },
+    #[error("Cannot read {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Cannot write {}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Cannot parse {}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml_edit::TomlError,
+    },
+    /// An existing TOML item does not have the type that is required to modify it.
+    /// `key` is the dotted path of the item (e.g. `build.rustflags`).
+    #[error("{key} in {} is not {expected}", path.display())]
+    InvalidType {
+        path: PathBuf,
+        key: String,
+        expected: &'static str,
+    },
+    #[error(
+        "The profile.{profile} table in {} does not exist or it does not specify `inherits`",
+        path.display()
+    )]
+    MissingInherits { path: PathBuf, profile: String },
+    /// `chain` contains the names of the profiles that form the cycle, starting and ending
+    /// with the same profile.
+    #[error("Profile inheritance cycle detected: {}", chain.join(" -> "))]
+    InheritanceCycle { chain: Vec<String> },
+    /// Templates applied together set different values of a rustflag or a config key, which are
+    /// shared by all profiles.
+    #[error(
+        "Templates for profiles {first_profile} and {second_profile} set different values of {setting} ({first_value} and {second_value}), which is shared by all profiles"
+    )]
+    SharedSettingConflict {
+        setting: String,
+        first_profile: String,
+        first_value: String,
+        second_profile: String,
+        second_value: String,
+    },
+    /// A value of a template cannot be expressed using environment variables.
+    #[error("{setting} cannot be set using the {name} environment variable, because {reason}")]
+    UnsupportedEnvValue {
+        setting: String,
+        name: String,
+        reason: &'static str,
+    },
+    #[error(
+        "The template contains options that are not supported by the workspace:\n{}",
+        errors.join("\n")
+    )]
+    UnsupportedItems { errors: Vec<String> },
+    #[error("Cannot get compiler version metadata")]
+    Toolchain(#[from] rustc_version::Error),
+    #[error("Cannot run `rustc` to {action}")]
+    Rustc {
+        action: &'static str,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("`rustc` failed to {action} ({status})\nStdout:\n{stdout}\n\nStderr:\n{stderr}")]
+    RustcFailed {
+        action: &'static str,
+        status: std::process::ExitStatus,
+        stdout: String,
+        stderr: String,
+    },
+}
+
+pub type WizardResult<T> = Result<T, WizardError>;
+
+fn format_metadata_path(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|path| format!(" for {}", path.display()))
+        .unwrap_or_default()
+}
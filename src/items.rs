@@ -0,0 +1,698 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::LazyLock;
+
+use crate::utils::find_program_path;
+use crate::{
+    BuiltinProfile, CustomItemId, CustomPossibleValue, ItemWarning, KnownCargoOptions,
+    MetadataBuilder, TemplateItemId, TemplateItemMetadata, TomlValue, TomlValueKind,
+};
+
+/// Location where the value of a template item is written when a template is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemLocation {
+    /// Key of the Cargo profile (e.g. `opt-level`).
+    Profile(&'static str),
+    /// Flag in `build.rustflags` of the Cargo config, written as `<key>=<value>`
+    /// (e.g. `-Ctarget-cpu` for `-Ctarget-cpu=native`).
+    Rustflag(&'static str),
+    /// Arbitrary flags added to `build.rustflags` of the Cargo config. Strings are split on
+    /// whitespace.
+    ExtraRustflags,
+    /// Key of a table in the Cargo config (e.g. `build` and `target` for `build.target`).
+    Config {
+        table: &'static str,
+        key: &'static str,
+    },
+    /// Key of a table in the Cargo config that contains an array. Strings are split on commas
+    /// (e.g. `std,panic_abort`).
+    ConfigList {
+        table: &'static str,
+        key: &'static str,
+    },
+}
+
+/// Item that can be a part of a template.
+///
+/// Implement this trait and register the item in an [`ItemRegistry`] to make custom items
+/// available in templates.
+pub trait TemplateItem: Send + Sync {
+    fn id(&self) -> TemplateItemId;
+
+    /// Name of the item shown to users (e.g. `Overflow checks`).
+    fn name(&self) -> &str;
+
+    /// Where the value of the item is written.
+    fn location(&self) -> ItemLocation;
+
+    /// Description of possible values of the item and of its requirements.
+    fn metadata(&self, options: &KnownCargoOptions) -> TemplateItemMetadata;
+
+    /// Value that Cargo uses when the item is not set in a profile based on the given built-in
+    /// profile. Only used for items stored in profiles.
+    fn default_value(&self, _profile: BuiltinProfile) -> Option<TomlValue> {
+        None
+    }
+}
+
+/// Set of items that can be used in templates.
+pub struct ItemRegistry {
+    items: Vec<Box<dyn TemplateItem>>,
+}
+
+impl ItemRegistry {
+    /// Creates a registry with the items that are built into `cargo-wizard`.
+    pub fn builtin() -> Self {
+        let items = BUILTIN_ITEMS
+            .iter()
+            .map(|item| Box::new(item) as Box<dyn TemplateItem>)
+            .collect();
+        Self { items }
+    }
+
+    /// Registers a new item. If an item with the same ID is already registered, it is replaced.
+    pub fn register<T: TemplateItem + 'static>(&mut self, item: T) {
+        let item: Box<dyn TemplateItem> = Box::new(item);
+        match self.items.iter_mut().find(|i| i.id() == item.id()) {
+            Some(existing) => *existing = item,
+            None => self.items.push(item),
+        }
+    }
+
+    pub fn get(&self, id: &TemplateItemId) -> Option<&dyn TemplateItem> {
+        self.items
+            .iter()
+            .find(|item| &item.id() == id)
+            .map(|item| item.as_ref())
+    }
+
+    /// IDs of all registered items, in the order of their registration.
+    pub fn ids(&self) -> Vec<TemplateItemId> {
+        self.items.iter().map(|item| item.id()).collect()
+    }
+
+    pub fn name(&self, id: &TemplateItemId) -> Option<&str> {
+        self.get(id).map(|item| item.name())
+    }
+
+    pub fn location(&self, id: &TemplateItemId) -> Option<ItemLocation> {
+        self.get(id).map(|item| item.location())
+    }
+
+    /// Returns the Cargo profile key of the given item, if it is stored in profiles.
+    pub fn profile_key(&self, id: &TemplateItemId) -> Option<&'static str> {
+        match self.location(id)? {
+            ItemLocation::Profile(key) => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Finds the item stored under the given Cargo profile key.
+    pub fn find_profile_item(&self, key: &str) -> Option<TemplateItemId> {
+        self.items
+            .iter()
+            .find(|item| matches!(item.location(), ItemLocation::Profile(k) if k == key))
+            .map(|item| item.id())
+    }
+
+    /// Returns the values of all profile items that Cargo uses by default in the given built-in
+    /// profile.
+    pub fn default_values(&self, profile: BuiltinProfile) -> Vec<(TemplateItemId, TomlValue)> {
+        self.items
+            .iter()
+            .filter_map(|item| Some((item.id(), item.default_value(profile)?)))
+            .collect()
+    }
+}
+
+impl Default for ItemRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Debug for ItemRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.ids()).finish()
+    }
+}
+
+/// Item built into `cargo-wizard`.
+struct BuiltinItem {
+    id: TemplateItemId,
+    name: &'static str,
+    location: ItemLocation,
+    metadata: fn(&KnownCargoOptions) -> TemplateItemMetadata,
+    /// Values that Cargo uses by default in the dev and release profiles.
+    defaults: Option<(TomlValue, TomlValue)>,
+}
+
+impl BuiltinItem {
+    fn new(
+        id: TemplateItemId,
+        name: &'static str,
+        location: ItemLocation,
+        metadata: fn(&KnownCargoOptions) -> TemplateItemMetadata,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            location,
+            metadata,
+            defaults: None,
+        }
+    }
+
+    fn defaults(mut self, dev: TomlValue, release: TomlValue) -> Self {
+        self.defaults = Some((dev, release));
+        self
+    }
+
+    fn default_for(&self, profile: BuiltinProfile) -> Option<TomlValue> {
+        let (dev, release) = self.defaults.as_ref()?;
+        match profile {
+            BuiltinProfile::Dev => Some(dev.clone()),
+            BuiltinProfile::Release => Some(release.clone()),
+        }
+    }
+}
+
+impl TemplateItem for &'static BuiltinItem {
+    fn id(&self) -> TemplateItemId {
+        self.id.clone()
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn location(&self) -> ItemLocation {
+        self.location
+    }
+
+    fn metadata(&self, options: &KnownCargoOptions) -> TemplateItemMetadata {
+        (self.metadata)(options)
+    }
+
+    fn default_value(&self, profile: BuiltinProfile) -> Option<TomlValue> {
+        self.default_for(profile)
+    }
+}
+
+/// Items built into `cargo-wizard`, in the order in which they are shown in the dialog.
+static BUILTIN_ITEMS: LazyLock<Vec<BuiltinItem>> = LazyLock::new(|| {
+    vec![
+        BuiltinItem::new(
+            TemplateItemId::OptimizationLevel,
+            "Optimization level",
+            ItemLocation::Profile("opt-level"),
+            |_| {
+                MetadataBuilder::default()
+                    .int("No optimizations", 0)
+                    .int("Basic optimizations", 1)
+                    .int("Some optimizations", 2)
+                    .int("All optimizations", 3)
+                    .string("Optimize for small size", "s")
+                    .string("Optimize for even smaller size", "z")
+                    .build()
+            },
+        )
+        .defaults(TomlValue::Int(0), TomlValue::Int(3)),
+        BuiltinItem::new(
+            TemplateItemId::Lto,
+            "Link-time optimizations",
+            ItemLocation::Profile("lto"),
+            |_| {
+                MetadataBuilder::default()
+                    .string("Disable LTO", "off")
+                    .value_min_version(1, 45)
+                    .bool("Thin local LTO", false)
+                    .string("Thin LTO", "thin")
+                    .bool("Fat LTO", true)
+                    .build()
+            },
+        )
+        .defaults(TomlValue::Bool(false), TomlValue::Bool(false)),
+        BuiltinItem::new(
+            TemplateItemId::CodegenUnits,
+            "Number of codegen units (CGUs)",
+            ItemLocation::Profile("codegen-units"),
+            |_| {
+                MetadataBuilder::default()
+                    .int("1 CGU", 1)
+                    .custom_value(TomlValueKind::Int)
+                    .build()
+            },
+        )
+        .defaults(TomlValue::Int(256), TomlValue::Int(16)),
+        BuiltinItem::new(
+            TemplateItemId::TargetCpuInstructionSet,
+            "Target CPU instruction set",
+            ItemLocation::Rustflag("-Ctarget-cpu"),
+            |options| {
+                MetadataBuilder::default()
+                    .string("Native (best for the local CPU)", "native")
+                    .custom_value(CustomPossibleValue::new(
+                        TomlValueKind::String,
+                        options.cpu_list().to_vec(),
+                    ))
+                    .on_applied(|value| {
+                        let TomlValue::String(value) = value else {
+                            return None;
+                        };
+                        if value == "native" {
+                            Some(ItemWarning::NativeTargetCpu {
+                                flag: "-Ctarget-cpu=native".to_string(),
+                            })
+                        } else {
+                            None
+                        }
+                    })
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::Panic,
+            "Panic handling mechanism",
+            ItemLocation::Profile("panic"),
+            |_| {
+                MetadataBuilder::default()
+                    .string("Unwind", "unwind")
+                    .string("Abort", "abort")
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::DebugInfo,
+            "Debug info",
+            ItemLocation::Profile("debug"),
+            |_| {
+                MetadataBuilder::default()
+                    .bool("Disable debuginfo", false)
+                    .string("Enable line directives", "line-directives-only")
+                    .value_min_version(1, 71)
+                    .string("Enable line tables", "line-tables-only")
+                    .value_min_version(1, 71)
+                    .int("Limited debuginfo", 1)
+                    .bool("Full debuginfo", true)
+                    .build()
+            },
+        )
+        .defaults(TomlValue::Bool(true), TomlValue::Bool(false)),
+        BuiltinItem::new(
+            TemplateItemId::SplitDebugInfo,
+            "Split debug info",
+            ItemLocation::Profile("split-debuginfo"),
+            |_| {
+                MetadataBuilder::default()
+                    .string("Off", "off")
+                    .string("Packed debuginfo", "packed")
+                    .string("Unpacked debuginfo", "unpacked")
+                    .min_version(1, 51)
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::Strip,
+            "Strip symbols",
+            ItemLocation::Profile("strip"),
+            |_| {
+                MetadataBuilder::default()
+                    .bool("Do not strip anything", false)
+                    .string("Strip debug info", "debuginfo")
+                    .string("Strip symbols", "symbols")
+                    .bool("Strip debug info and symbols", true)
+                    .min_version(1, 59)
+                    .build()
+            },
+        )
+        .defaults(
+            TomlValue::String("none".to_string()),
+            TomlValue::String("none".to_string()),
+        ),
+        BuiltinItem::new(
+            TemplateItemId::Incremental,
+            "Incremental compilation",
+            ItemLocation::Profile("incremental"),
+            |_| {
+                MetadataBuilder::default()
+                    .bool("Enable", true)
+                    .bool("Disable", false)
+                    .build()
+            },
+        )
+        .defaults(TomlValue::Bool(true), TomlValue::Bool(false)),
+        BuiltinItem::new(
+            TemplateItemId::Linker,
+            "Linker",
+            ItemLocation::Rustflag("-Clink-arg=-fuse-ld"),
+            |_| {
+                MetadataBuilder::default()
+                    .string(&linker_description("lld", "LLD"), "lld")
+                    .string(&linker_description("mold", "MOLD"), "mold")
+                    .requires_unix()
+                    .on_applied(|value| {
+                        let TomlValue::String(linker) = value else {
+                            return None;
+                        };
+                        if find_program_path(linker).is_none() {
+                            Some(ItemWarning::MissingLinker {
+                                linker: linker.clone(),
+                                install_command: format!("sudo apt install {linker}"),
+                            })
+                        } else {
+                            None
+                        }
+                    })
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::CodegenBackend,
+            "Codegen backend",
+            ItemLocation::Profile("codegen-backend"),
+            |_| {
+                MetadataBuilder::default()
+                    .string("Cranelift", "cranelift")
+                    .requires_nightly()
+                    .on_applied(|value| {
+                        if value == &TomlValue::String("cranelift".to_string()) {
+                            Some(ItemWarning::MissingCodegenBackend {
+                                backend: "Cranelift".to_string(),
+                                install_command: "rustup component add rustc-codegen-cranelift-preview --toolchain nightly".to_string(),
+                            })
+                        } else {
+                            None
+                        }
+                    })
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::FrontendThreads,
+            "Number of frontend threads",
+            ItemLocation::Rustflag("-Zthreads"),
+            |options| {
+                MetadataBuilder::default()
+                    .int(
+                        &format!("{} (local core count)", options.core_count()),
+                        options.core_count(),
+                    )
+                    .requires_nightly()
+                    .custom_value(TomlValueKind::Int)
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::ForceFramePointers,
+            "Force frame pointers",
+            ItemLocation::Rustflag("-Cforce-frame-pointers"),
+            |_| {
+                MetadataBuilder::default()
+                    .bool("Keep frame pointers", true)
+                    .bool("Omit frame pointers", false)
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::RelocationModel,
+            "Relocation model",
+            ItemLocation::Rustflag("-Crelocation-model"),
+            |_| {
+                MetadataBuilder::default()
+                    .string("Position-independent code", "pic")
+                    .string("Position-independent executable", "pie")
+                    .string("Static (non-relocatable code)", "static")
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::EmbedBitcode,
+            "Embed LLVM bitcode",
+            ItemLocation::Rustflag("-Cembed-bitcode"),
+            |_| {
+                MetadataBuilder::default()
+                    .bool("Embed LLVM bitcode", true)
+                    .bool("Do not embed LLVM bitcode", false)
+                    .min_version(1, 45)
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::ShareGenerics,
+            "Share generics",
+            ItemLocation::Rustflag("-Zshare-generics"),
+            |_| {
+                MetadataBuilder::default()
+                    .bool("Share generics between crates", true)
+                    .bool("Do not share generics", false)
+                    .requires_nightly()
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::LocationDetail,
+            "Panic location details",
+            ItemLocation::Rustflag("-Zlocation-detail"),
+            |_| {
+                MetadataBuilder::default()
+                    .string("No location details", "none")
+                    .string("File name only", "file")
+                    .string("File, line and column", "file,line,column")
+                    .requires_nightly()
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::FmtDebug,
+            "Debug formatting",
+            ItemLocation::Rustflag("-Zfmt-debug"),
+            |_| {
+                MetadataBuilder::default()
+                    .string("Remove `Debug` implementations", "none")
+                    .string("Only print type names", "shallow")
+                    .string("Full `Debug` output", "full")
+                    .requires_nightly()
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::BuildStd,
+            "Rebuild standard library",
+            ItemLocation::ConfigList {
+                table: "unstable",
+                key: "build-std",
+            },
+            |_| {
+                MetadataBuilder::default()
+                    .string("Standard library and abort runtime", "std,panic_abort")
+                    .string("Standard library", "std")
+                    .string("Core and alloc (no_std)", "core,alloc")
+                    .custom_value(TomlValueKind::String)
+                    .requires_nightly()
+                    .on_applied(|_| {
+                        Some(ItemWarning::MissingStdSource {
+                            install_command: "rustup component add rust-src --toolchain nightly"
+                                .to_string(),
+                        })
+                    })
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::BuildStdFeatures,
+            "Standard library features",
+            ItemLocation::ConfigList {
+                table: "unstable",
+                key: "build-std-features",
+            },
+            |_| {
+                MetadataBuilder::default()
+                    .custom_value(TomlValueKind::String)
+                    .requires_nightly()
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::BuildTarget,
+            "Build target",
+            ItemLocation::Config {
+                table: "build",
+                key: "target",
+            },
+            |options| {
+                let mut builder = MetadataBuilder::default();
+                if let Some(host) = options.host() {
+                    builder = builder.string("Host target", host);
+                }
+                builder
+                    .custom_value(TomlValueKind::String)
+                    .on_applied(|value| {
+                        let TomlValue::String(target) = value else {
+                            return None;
+                        };
+                        Some(ItemWarning::ArtifactDirectory {
+                            directory: format!("target/{target}"),
+                        })
+                    })
+                    .build()
+            },
+        ),
+        BuiltinItem::new(
+            TemplateItemId::ExtraRustflags,
+            "Extra rustflags",
+            ItemLocation::ExtraRustflags,
+            |_| {
+                MetadataBuilder::default()
+                    .list_value(&[
+                        "-Cforce-frame-pointers=yes",
+                        "-Csymbol-mangling-version=v0",
+                        "-Zshare-generics",
+                    ])
+                    .build()
+            },
+        ),
+    ]
+});
+
+/// Returns the values of built-in profile items that Cargo uses by default in the given built-in
+/// profile.
+pub(crate) fn builtin_default_values(profile: BuiltinProfile) -> Vec<(TemplateItemId, TomlValue)> {
+    BUILTIN_ITEMS
+        .iter()
+        .filter_map(|item| Some((item.id.clone(), item.default_for(profile)?)))
+        .collect()
+}
+
+impl Display for TemplateItemId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateItemId::Custom(CustomItemId(id)) => f.write_str(id),
+            id => {
+                let item = BUILTIN_ITEMS.iter().find(|item| &item.id == id);
+                f.write_str(item.map_or("<unknown item>", |item| item.name))
+            }
+        }
+    }
+}
+
+fn linker_description(path: &str, name: &str) -> String {
+    find_program_path(path)
+        .and_then(|p| p.to_str().map(|s| s.to_string()))
+        .map(|s| format!("{name} (found at {s})"))
+        .unwrap_or_else(|| format!("{name} (not found)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::template::TemplateBuilder;
+    use crate::{
+        BuiltinProfile, CargoManifest, CustomItemId, ItemLocation, ItemRegistry, KnownCargoOptions,
+        Profile, TemplateItem, TemplateItemId, TemplateItemMetadata, TomlValue, WizardOptions,
+    };
+
+    struct OverflowChecks;
+
+    impl TemplateItem for OverflowChecks {
+        fn id(&self) -> TemplateItemId {
+            TemplateItemId::Custom(CustomItemId::new("overflow-checks"))
+        }
+
+        fn name(&self) -> &str {
+            "Overflow checks"
+        }
+
+        fn location(&self) -> ItemLocation {
+            ItemLocation::Profile("overflow-checks")
+        }
+
+        fn metadata(&self, _options: &KnownCargoOptions) -> TemplateItemMetadata {
+            TemplateItemMetadata::builder()
+                .bool("Enable", true)
+                .bool("Disable", false)
+                .build()
+        }
+
+        fn default_value(&self, profile: BuiltinProfile) -> Option<TomlValue> {
+            Some(TomlValue::bool(matches!(profile, BuiltinProfile::Dev)))
+        }
+    }
+
+    #[test]
+    fn apply_custom_profile_item() {
+        let mut registry = ItemRegistry::builtin();
+        registry.register(OverflowChecks);
+        let options = WizardOptions::default().with_item_registry(registry);
+
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::Custom(CustomItemId::new("overflow-checks")),
+                TomlValue::bool(true),
+            )
+            .item(TemplateItemId::Lto, TomlValue::bool(true))
+            .build();
+        let manifest = create_manifest("")
+            .apply_template(&Profile::release(), &template, &options)
+            .unwrap();
+        insta::assert_snapshot!(manifest.get_text(), @r###"
+        [profile.release]
+        overflow-checks = true
+        lto = true
+        "###);
+    }
+
+    #[test]
+    fn skip_default_value_of_custom_item() {
+        let mut registry = ItemRegistry::builtin();
+        registry.register(OverflowChecks);
+        let options = WizardOptions::default().with_item_registry(registry);
+
+        let template = TemplateBuilder::new(BuiltinProfile::Dev)
+            .item(
+                TemplateItemId::Custom(CustomItemId::new("overflow-checks")),
+                TomlValue::bool(true),
+            )
+            .build();
+        let manifest = create_manifest("")
+            .apply_template(&Profile::dev(), &template, &options)
+            .unwrap();
+        insta::assert_snapshot!(manifest.get_text(), @r###"
+        [profile.dev]
+        "###);
+    }
+
+    #[test]
+    fn register_item_twice() {
+        let mut registry = ItemRegistry::builtin();
+        let count = registry.ids().len();
+        registry.register(OverflowChecks);
+        registry.register(OverflowChecks);
+        assert_eq!(registry.ids().len(), count + 1);
+        assert_eq!(
+            registry.find_profile_item("overflow-checks"),
+            Some(TemplateItemId::Custom(CustomItemId::new("overflow-checks")))
+        );
+    }
+
+    #[test]
+    fn custom_item_name() {
+        let mut registry = ItemRegistry::builtin();
+        registry.register(OverflowChecks);
+        assert_eq!(
+            registry.name(&TemplateItemId::Custom(CustomItemId::new(
+                "overflow-checks"
+            ))),
+            Some("Overflow checks")
+        );
+        assert_eq!(
+            registry.name(&TemplateItemId::Lto),
+            Some("Link-time optimizations")
+        );
+    }
+
+    fn create_manifest(text: &str) -> CargoManifest {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        std::fs::write(&path, text).unwrap();
+        CargoManifest::from_path(&path).unwrap()
+    }
+}
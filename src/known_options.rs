@@ -0,0 +1,826 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use rustc_version::Version;
+
+use crate::{
+    ItemRegistry, Template, TemplateItemId, TomlValue, WizardError, WizardOptions, WizardResult,
+    get_core_count, rustc_command,
+};
+
+#[derive(Copy, Clone)]
+pub enum TomlValueKind {
+    Int,
+    String,
+    Array,
+    Table,
+}
+
+impl TomlValueKind {
+    pub fn matches_value(&self, value: &TomlValue) -> bool {
+        matches!(
+            (self, value),
+            (TomlValueKind::Int, TomlValue::Int(_))
+                | (TomlValueKind::String, TomlValue::String(_))
+                | (TomlValueKind::Array, TomlValue::Array(_))
+                | (TomlValueKind::Table, TomlValue::Table(_))
+        )
+    }
+}
+
+pub enum SelectedPossibleValue {
+    Constant { index: usize },
+    Custom { value: TomlValue },
+    None,
+}
+
+type OnAppliedCallback = dyn Fn(&TomlValue) -> Option<ItemWarning>;
+
+/// Warning about a template item that should be shown to the user after the item is applied.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ItemWarning {
+    /// Code compiled for the native CPU might not work on other machines.
+    NativeTargetCpu { flag: String },
+    /// A codegen backend has to be installed to use the item.
+    MissingCodegenBackend {
+        backend: String,
+        install_command: String,
+    },
+    /// A linker has to be installed to use the item.
+    MissingLinker {
+        linker: String,
+        install_command: String,
+    },
+    /// The source code of the standard library has to be installed to rebuild it.
+    MissingStdSource { install_command: String },
+    /// Build artifacts will be placed in a different directory.
+    ArtifactDirectory { directory: String },
+    /// Warning of a custom template item.
+    Custom { message: String },
+}
+
+impl ItemWarning {
+    /// Formats the warning, passing its flags, commands and paths through `highlight`, so that
+    /// they can be e.g. styled when printed to a terminal.
+    pub fn format_with(&self, highlight: impl Fn(&str) -> String) -> String {
+        match self {
+            ItemWarning::NativeTargetCpu { flag } => format!(
+                "You are using {}. Code compiled using this flag might not work on other machines! Be careful if you distribute binaries compiled using this flag.",
+                highlight(flag)
+            ),
+            ItemWarning::MissingCodegenBackend {
+                backend,
+                install_command,
+            } => format!(
+                "Do not forget to install the {backend} codegen backend using `{}`.",
+                highlight(install_command)
+            ),
+            ItemWarning::MissingLinker {
+                linker,
+                install_command,
+            } => format!(
+                "Do not forget to install the {} linker, e.g. using `{}`.",
+                highlight(linker),
+                highlight(install_command)
+            ),
+            ItemWarning::MissingStdSource { install_command } => format!(
+                "Rebuilding the standard library requires the rust-src component, install it using `{}`.",
+                highlight(install_command)
+            ),
+            ItemWarning::ArtifactDirectory { directory } => {
+                format!(
+                    "Build artifacts will be placed in `{}`.",
+                    highlight(directory)
+                )
+            }
+            ItemWarning::Custom { message } => message.clone(),
+        }
+    }
+}
+
+impl Display for ItemWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format_with(|text| text.to_string()))
+    }
+}
+
+pub struct TemplateItemMetadata {
+    values: Vec<PossibleValue>,
+    custom_value: Option<CustomPossibleValue>,
+    list_value: Option<ListPossibleValue>,
+    flags: HashSet<ItemFlag>,
+    min_version: Option<Version>,
+    on_applied: Option<Box<OnAppliedCallback>>,
+}
+
+impl TemplateItemMetadata {
+    pub fn builder() -> MetadataBuilder {
+        MetadataBuilder::default()
+    }
+
+    pub fn get_selected_value(&self, value: TomlValue) -> SelectedPossibleValue {
+        if let Some(index) = self.values.iter().position(|v| v.value == value) {
+            return SelectedPossibleValue::Constant { index };
+        } else if let Some(custom) = &self.custom_value
+            && custom.kind().matches_value(&value)
+        {
+            return SelectedPossibleValue::Custom { value };
+        }
+        SelectedPossibleValue::None
+    }
+
+    pub fn get_possible_values(&self) -> &[PossibleValue] {
+        &self.values
+    }
+
+    pub fn get_custom_value(&self) -> Option<&CustomPossibleValue> {
+        self.custom_value.as_ref()
+    }
+
+    /// Returns the list value of the item, if the item holds a list of arbitrary entries.
+    pub fn get_list_value(&self) -> Option<&ListPossibleValue> {
+        self.list_value.as_ref()
+    }
+
+    pub fn requires_nightly(&self) -> bool {
+        self.flags.contains(&ItemFlag::RequiresNightly)
+    }
+
+    pub fn requires_unix(&self) -> bool {
+        self.flags.contains(&ItemFlag::RequiresUnix)
+    }
+
+    /// Minimum stable Cargo version that supports this item.
+    pub fn min_version(&self) -> Option<&Version> {
+        self.min_version.as_ref()
+    }
+
+    /// Minimum stable Cargo version that supports the given value of this item.
+    pub fn required_version(&self, value: &TomlValue) -> Option<&Version> {
+        let value_version = self
+            .values
+            .iter()
+            .find(|v| &v.value == value)
+            .and_then(|v| v.min_version.as_ref());
+        self.min_version.as_ref().max(value_version)
+    }
+
+    /// Returns a warning that should be shown after the given value of the item is applied.
+    pub fn on_applied(&self, value: &TomlValue) -> Option<ItemWarning> {
+        self.on_applied
+            .as_ref()
+            .and_then(|callback| callback(value))
+    }
+}
+
+pub struct CustomPossibleValue {
+    kind: TomlValueKind,
+    possible_entries: Vec<String>,
+}
+
+impl CustomPossibleValue {
+    pub fn new(kind: TomlValueKind, possible_entries: Vec<String>) -> Self {
+        Self {
+            kind,
+            possible_entries,
+        }
+    }
+
+    pub fn kind(&self) -> TomlValueKind {
+        self.kind
+    }
+
+    pub fn possible_entries(&self) -> &[String] {
+        &self.possible_entries
+    }
+}
+
+impl From<TomlValueKind> for CustomPossibleValue {
+    fn from(kind: TomlValueKind) -> Self {
+        Self {
+            kind,
+            possible_entries: vec![],
+        }
+    }
+}
+
+/// List of arbitrary string entries, stored as an array of strings.
+pub struct ListPossibleValue {
+    possible_entries: Vec<String>,
+}
+
+impl ListPossibleValue {
+    pub fn possible_entries(&self) -> &[String] {
+        &self.possible_entries
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+enum ItemFlag {
+    RequiresNightly,
+    RequiresUnix,
+}
+
+/// Builder of [`TemplateItemMetadata`].
+#[derive(Default)]
+pub struct MetadataBuilder {
+    values: Vec<PossibleValue>,
+    custom_value: Option<CustomPossibleValue>,
+    list_value: Option<ListPossibleValue>,
+    flags: HashSet<ItemFlag>,
+    min_version: Option<Version>,
+    on_applied: Option<Box<OnAppliedCallback>>,
+}
+
+impl MetadataBuilder {
+    pub fn build(self) -> TemplateItemMetadata {
+        let MetadataBuilder {
+            values,
+            custom_value,
+            list_value,
+            flags,
+            min_version,
+            on_applied,
+        } = self;
+        TemplateItemMetadata {
+            values,
+            custom_value,
+            list_value,
+            flags,
+            min_version,
+            on_applied,
+        }
+    }
+
+    pub fn value(mut self, description: &str, value: TomlValue) -> Self {
+        self.values.push(PossibleValue::new(description, value));
+        self
+    }
+
+    pub fn int(self, description: &str, value: i64) -> Self {
+        self.value(description, TomlValue::Int(value))
+    }
+
+    pub fn bool(self, description: &str, value: bool) -> Self {
+        self.value(description, TomlValue::Bool(value))
+    }
+
+    pub fn string(self, description: &str, value: &str) -> Self {
+        self.value(description, TomlValue::String(value.to_string()))
+    }
+
+    pub fn custom_value<V: Into<CustomPossibleValue>>(mut self, value: V) -> Self {
+        self.custom_value = Some(value.into());
+        self
+    }
+
+    pub fn list_value(mut self, possible_entries: &[&str]) -> Self {
+        self.list_value = Some(ListPossibleValue {
+            possible_entries: possible_entries.iter().map(|s| s.to_string()).collect(),
+        });
+        self
+    }
+
+    pub fn requires_nightly(mut self) -> Self {
+        self.flags.insert(ItemFlag::RequiresNightly);
+        self
+    }
+
+    pub fn requires_unix(mut self) -> Self {
+        self.flags.insert(ItemFlag::RequiresUnix);
+        self
+    }
+
+    /// Sets the minimum stable Cargo version that supports the item.
+    pub fn min_version(mut self, major: u64, minor: u64) -> Self {
+        self.min_version = Some(Version::new(major, minor, 0));
+        self
+    }
+
+    /// Sets the minimum stable Cargo version that supports the most recently added value.
+    pub fn value_min_version(mut self, major: u64, minor: u64) -> Self {
+        let value = self
+            .values
+            .last_mut()
+            .expect("No value to set the minimum version for");
+        value.min_version = Some(Version::new(major, minor, 0));
+        self
+    }
+
+    pub fn on_applied<F: Fn(&TomlValue) -> Option<ItemWarning> + 'static>(mut self, f: F) -> Self {
+        self.on_applied = Some(Box::new(f));
+        self
+    }
+}
+
+fn get_target_cpu_list(workspace_root: &Path) -> WizardResult<Vec<String>> {
+    const ACTION: &str = "find `target-cpus` list";
+
+    let output = rustc_command(workspace_root)
+        .args(["--print", "target-cpus"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|child| child.wait_with_output())
+        .map_err(|source| WizardError::Rustc {
+            action: ACTION,
+            source,
+        })?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() {
+        return Err(WizardError::RustcFailed {
+            action: ACTION,
+            status: output.status,
+            stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    Ok(parse_target_cpu_list(&stdout))
+}
+
+fn parse_target_cpu_list(input: &str) -> Vec<String> {
+    input
+        .lines()
+        .skip(1)
+        .filter_map(|l| l.trim().split_ascii_whitespace().next())
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Known options from Cargo, containing descriptions and possible values.
+pub struct KnownCargoOptions {
+    registry: Arc<ItemRegistry>,
+    core_count: i64,
+    cpu_list: Vec<String>,
+    host: Option<String>,
+    options: WizardOptions,
+}
+
+impl KnownCargoOptions {
+    pub fn create(workspace_root: &Path, options: &WizardOptions) -> WizardResult<Self> {
+        let toolchain = options.toolchain();
+        let core_count = get_core_count();
+        let cpu_list = get_target_cpu_list(workspace_root)?;
+        Ok(Self {
+            registry: options.item_registry_arc(),
+            core_count,
+            cpu_list,
+            host: toolchain.map(|toolchain| toolchain.host().to_string()),
+            options: options.clone(),
+        })
+    }
+
+    /// Checks if options that require the given Cargo version can be used in the workspace.
+    pub fn check_version(&self, required: &Version) -> Result<(), String> {
+        self.options.check_version(required)
+    }
+
+    pub fn is_version_supported(&self, required: Option<&Version>) -> bool {
+        required.is_none_or(|version| self.check_version(version).is_ok())
+    }
+
+    /// Checks that all items of the template are supported by the Cargo version of the workspace.
+    pub fn validate_template(&self, template: &Template) -> WizardResult<()> {
+        let mut errors: Vec<String> = template
+            .iter_items()
+            .filter_map(|(id, value)| {
+                let metadata = self.get_metadata(id);
+                let required = metadata.required_version(value)?;
+                self.check_version(required).err().map(|reason| {
+                    format!(
+                        "`{}` set to `{}` requires Cargo {required} or newer, but {reason}",
+                        self.get_name(id),
+                        value.to_toml_value()
+                    )
+                })
+            })
+            .collect();
+        if template.get_item(&TemplateItemId::BuildStd).is_some()
+            && template.get_item(&TemplateItemId::BuildTarget).is_none()
+        {
+            errors.push(format!(
+                "`{}` requires `{}` to be set, because Cargo can only rebuild the standard library for an explicit target",
+                TemplateItemId::BuildStd,
+                TemplateItemId::BuildTarget
+            ));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(WizardError::UnsupportedItems { errors })
+        }
+    }
+
+    /// IDs of all items that can be used in templates.
+    pub fn get_all_ids(&self) -> Vec<TemplateItemId> {
+        self.registry.ids()
+    }
+
+    /// Returns the name of the given item. Items that are not registered are named by their ID.
+    pub fn get_name(&self, id: &TemplateItemId) -> String {
+        match self.registry.name(id) {
+            Some(name) => name.to_string(),
+            None => id.to_string(),
+        }
+    }
+
+    pub(crate) fn core_count(&self) -> i64 {
+        self.core_count
+    }
+
+    pub(crate) fn cpu_list(&self) -> &[String] {
+        &self.cpu_list
+    }
+
+    pub(crate) fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Returns the metadata of the given item. Items that are not registered have no possible
+    /// values.
+    pub fn get_metadata(&self, id: &TemplateItemId) -> TemplateItemMetadata {
+        match self.registry.get(id) {
+            Some(item) => item.metadata(self),
+            None => TemplateItemMetadata::builder().build(),
+        }
+    }
+}
+
+/// Possible value of a Cargo profile or a Cargo config, along with a description of what it does.
+#[derive(Debug, Clone)]
+pub struct PossibleValue {
+    description: String,
+    value: TomlValue,
+    min_version: Option<Version>,
+}
+
+impl PossibleValue {
+    fn new(description: &str, value: TomlValue) -> Self {
+        Self {
+            value,
+            description: description.to_string(),
+            min_version: None,
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn value(&self) -> &TomlValue {
+        &self.value
+    }
+
+    /// Minimum stable Cargo version that supports this value.
+    pub fn min_version(&self) -> Option<&Version> {
+        self.min_version.as_ref()
+    }
+}
+
+/// Test that the predefined templates can be created without panicking.
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rustc_version::Version;
+
+    use crate::{TemplateItemId, TomlValue, WizardOptions, fast_runtime_template};
+
+    use crate::known_options::{ItemWarning, KnownCargoOptions, parse_target_cpu_list};
+
+    #[test]
+    fn get_profile_id_possible_values() {
+        let options = KnownCargoOptions::create(Path::new("."), &WizardOptions::default()).unwrap();
+        for id in options.get_all_ids() {
+            let metadata = options.get_metadata(&id);
+            assert!(
+                !metadata.get_possible_values().is_empty()
+                    || metadata.get_custom_value().is_some()
+                    || metadata
+                        .get_list_value()
+                        .is_some_and(|list| !list.possible_entries().is_empty())
+            );
+        }
+    }
+
+    #[test]
+    fn required_version_of_value() {
+        let options = KnownCargoOptions::create(Path::new("."), &WizardOptions::default()).unwrap();
+        let debuginfo = options.get_metadata(&TemplateItemId::DebugInfo);
+        assert_eq!(debuginfo.required_version(&TomlValue::Bool(true)), None);
+        assert_eq!(
+            debuginfo.required_version(&TomlValue::string("line-tables-only")),
+            Some(&Version::new(1, 71, 0))
+        );
+
+        let strip = options.get_metadata(&TemplateItemId::Strip);
+        assert_eq!(
+            strip.required_version(&TomlValue::Bool(true)),
+            Some(&Version::new(1, 59, 0))
+        );
+    }
+
+    #[test]
+    fn check_version_against_rust_version() {
+        let options = KnownCargoOptions::create(
+            Path::new("."),
+            &WizardOptions::default().with_rust_version(Version::new(1, 60, 0)),
+        )
+        .unwrap();
+        assert!(options.check_version(&Version::new(1, 59, 0)).is_ok());
+        assert!(options.check_version(&Version::new(1, 71, 0)).is_err());
+    }
+
+    #[test]
+    fn build_std_requires_target() {
+        let options = KnownCargoOptions::create(Path::new("."), &WizardOptions::default()).unwrap();
+        let mut template = fast_runtime_template();
+        template.insert_item(TemplateItemId::BuildStd, TomlValue::string("std"));
+        assert!(options.validate_template(&template).is_err());
+
+        template.insert_item(
+            TemplateItemId::BuildTarget,
+            TomlValue::string("x86_64-unknown-linux-gnu"),
+        );
+        assert!(options.validate_template(&template).is_ok());
+    }
+
+    #[test]
+    fn structured_on_applied_warnings() {
+        let options = KnownCargoOptions::create(Path::new("."), &WizardOptions::default()).unwrap();
+        let warning = options
+            .get_metadata(&TemplateItemId::BuildTarget)
+            .on_applied(&TomlValue::string("x86_64-unknown-linux-gnu"))
+            .unwrap();
+        assert_eq!(
+            warning,
+            ItemWarning::ArtifactDirectory {
+                directory: "target/x86_64-unknown-linux-gnu".to_string()
+            }
+        );
+        insta::assert_snapshot!(warning.to_string(), @"Build artifacts will be placed in `target/x86_64-unknown-linux-gnu`.");
+
+        let cpu = options.get_metadata(&TemplateItemId::TargetCpuInstructionSet);
+        assert!(matches!(
+            cpu.on_applied(&TomlValue::string("native")),
+            Some(ItemWarning::NativeTargetCpu { .. })
+        ));
+        assert_eq!(cpu.on_applied(&TomlValue::string("znver4")), None);
+    }
+
+    #[test]
+    fn test_parse_target_cpu_list() {
+        let cpu_list = parse_target_cpu_list(
+            r#"Available CPUs for this target:
+    native                  - Select the CPU of the current host (currently icelake-client).
+    alderlake
+    amdfam10
+    athlon
+    athlon-4
+    athlon-fx
+    athlon-mp
+    athlon-tbird
+    athlon-xp
+    athlon64
+    athlon64-sse3
+    atom
+    atom_sse4_2
+    atom_sse4_2_movbe
+    barcelona
+    bdver1
+    bdver2
+    bdver3
+    bdver4
+    bonnell
+    broadwell
+    btver1
+    btver2
+    c3
+    c3-2
+    cannonlake
+    cascadelake
+    cooperlake
+    core-avx-i
+    core-avx2
+    core2
+    core_2_duo_sse4_1
+    core_2_duo_ssse3
+    core_2nd_gen_avx
+    core_3rd_gen_avx
+    core_4th_gen_avx
+    core_4th_gen_avx_tsx
+    core_5th_gen_avx
+    core_5th_gen_avx_tsx
+    core_aes_pclmulqdq
+    core_i7_sse4_2
+    corei7
+    corei7-avx
+    emeraldrapids
+    generic
+    geode
+    goldmont
+    goldmont-plus
+    goldmont_plus
+    grandridge
+    graniterapids
+    graniterapids-d
+    graniterapids_d
+    haswell
+    i386
+    i486
+    i586
+    i686
+    icelake-client
+    icelake-server
+    icelake_client
+    icelake_server
+    ivybridge
+    k6
+    k6-2
+    k6-3
+    k8
+    k8-sse3
+    knl
+    knm
+    lakemont
+    meteorlake
+    mic_avx512
+    nehalem
+    nocona
+    opteron
+    opteron-sse3
+    penryn
+    pentium
+    pentium-m
+    pentium-mmx
+    pentium2
+    pentium3
+    pentium3m
+    pentium4
+    pentium4m
+    pentium_4
+    pentium_4_sse3
+    pentium_ii
+    pentium_iii
+    pentium_iii_no_xmm_regs
+    pentium_m
+    pentium_mmx
+    pentium_pro
+    pentiumpro
+    prescott
+    raptorlake
+    rocketlake
+    sandybridge
+    sapphirerapids
+    sierraforest
+    silvermont
+    skx
+    skylake
+    skylake-avx512
+    skylake_avx512
+    slm
+    tigerlake
+    tremont
+    westmere
+    winchip-c6
+    winchip2
+    x86-64                  - This is the default target CPU for the current build target (currently x86_64-unknown-linux-gnu).
+    x86-64-v2
+    x86-64-v3
+    x86-64-v4
+    yonah
+    znver1
+    znver2
+    znver3
+    znver4
+"#,
+        );
+        insta::assert_debug_snapshot!(cpu_list, @r###"
+        [
+            "native",
+            "alderlake",
+            "amdfam10",
+            "athlon",
+            "athlon-4",
+            "athlon-fx",
+            "athlon-mp",
+            "athlon-tbird",
+            "athlon-xp",
+            "athlon64",
+            "athlon64-sse3",
+            "atom",
+            "atom_sse4_2",
+            "atom_sse4_2_movbe",
+            "barcelona",
+            "bdver1",
+            "bdver2",
+            "bdver3",
+            "bdver4",
+            "bonnell",
+            "broadwell",
+            "btver1",
+            "btver2",
+            "c3",
+            "c3-2",
+            "cannonlake",
+            "cascadelake",
+            "cooperlake",
+            "core-avx-i",
+            "core-avx2",
+            "core2",
+            "core_2_duo_sse4_1",
+            "core_2_duo_ssse3",
+            "core_2nd_gen_avx",
+            "core_3rd_gen_avx",
+            "core_4th_gen_avx",
+            "core_4th_gen_avx_tsx",
+            "core_5th_gen_avx",
+            "core_5th_gen_avx_tsx",
+            "core_aes_pclmulqdq",
+            "core_i7_sse4_2",
+            "corei7",
+            "corei7-avx",
+            "emeraldrapids",
+            "generic",
+            "geode",
+            "goldmont",
+            "goldmont-plus",
+            "goldmont_plus",
+            "grandridge",
+            "graniterapids",
+            "graniterapids-d",
+            "graniterapids_d",
+            "haswell",
+            "i386",
+            "i486",
+            "i586",
+            "i686",
+            "icelake-client",
+            "icelake-server",
+            "icelake_client",
+            "icelake_server",
+            "ivybridge",
+            "k6",
+            "k6-2",
+            "k6-3",
+            "k8",
+            "k8-sse3",
+            "knl",
+            "knm",
+            "lakemont",
+            "meteorlake",
+            "mic_avx512",
+            "nehalem",
+            "nocona",
+            "opteron",
+            "opteron-sse3",
+            "penryn",
+            "pentium",
+            "pentium-m",
+            "pentium-mmx",
+            "pentium2",
+            "pentium3",
+            "pentium3m",
+            "pentium4",
+            "pentium4m",
+            "pentium_4",
+            "pentium_4_sse3",
+            "pentium_ii",
+            "pentium_iii",
+            "pentium_iii_no_xmm_regs",
+            "pentium_m",
+            "pentium_mmx",
+            "pentium_pro",
+            "pentiumpro",
+            "prescott",
+            "raptorlake",
+            "rocketlake",
+            "sandybridge",
+            "sapphirerapids",
+            "sierraforest",
+            "silvermont",
+            "skx",
+            "skylake",
+            "skylake-avx512",
+            "skylake_avx512",
+            "slm",
+            "tigerlake",
+            "tremont",
+            "westmere",
+            "winchip-c6",
+            "winchip2",
+            "x86-64",
+            "x86-64-v2",
+            "x86-64-v3",
+            "x86-64-v4",
+            "yonah",
+            "znver1",
+            "znver2",
+            "znver3",
+            "znver4",
+        ]
+        "###);
+    }
+}
@@ -10,17 +10,46 @@
 //! ```
 //!
 //! You can also use this crate as a library, although it probably won't be very useful.
+//! With the `serde` feature enabled, templates can be serialized and deserialized. The serialized
+//! form contains a format version (`TEMPLATE_FORMAT_VERSION`), so that templates saved by older
+//! versions of this crate stay readable.
+//!
+//! Custom template items can be added by implementing [`TemplateItem`] and registering the item in
+//! an [`ItemRegistry`], which is then passed to [`WizardOptions::with_item_registry`].
 
+pub use args::{format_cargo_invocation, template_config_args};
+pub use audit::{AuditIssue, ProfileAudit, TemplateMatch, audit_profiles};
+pub use env::{EnvFormat, EnvVar, format_env_vars, template_env_vars};
+pub use error::{WizardError, WizardResult};
+pub use items::{ItemLocation, ItemRegistry, TemplateItem};
+pub use known_options::{
+    CustomPossibleValue, ItemWarning, KnownCargoOptions, ListPossibleValue, MetadataBuilder,
+    PossibleValue, SelectedPossibleValue, TemplateItemMetadata, TomlValueKind,
+};
 pub use predefined::*;
-pub use template::{Template, TemplateItemId, WizardOptions};
+#[cfg(feature = "serde")]
+pub use template::TEMPLATE_FORMAT_VERSION;
+pub use template::{CustomItemId, Template, TemplateItemId, WizardOptions};
 pub use toml::TomlValue;
+pub use toolchain::{Toolchain, rustc_command};
 pub use utils::get_core_count;
-pub use workspace::config::CargoConfig;
-pub use workspace::manifest::{BuiltinProfile, CargoManifest, Profile, resolve_manifest_path};
+pub use workspace::config::{CargoConfig, ConfigLocation, ConfigLocationKind, config_locations};
+pub use workspace::manifest::{
+    BuiltinProfile, CargoManifest, Profile, WorkspaceManifests, resolve_manifest_path,
+};
+pub use workspace::rustflags::{RustflagsOverride, find_rustflags_overrides};
+pub use workspace::toolchain_file::ToolchainFile;
 pub use workspace::{CargoWorkspace, ModificationResult, ModifiedWorkspace, parse_workspace};
 
+mod args;
+mod audit;
+mod env;
+mod error;
+mod items;
+mod known_options;
 mod predefined;
 mod template;
 mod toml;
+mod toolchain;
 mod utils;
 mod workspace;
@@ -1,18 +1,23 @@
+use std::ffi::OsStr;
 use std::io::IsTerminal;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::Context;
-use clap::Parser;
-use rustc_version::Channel;
+use clap::builder::{EnumValueParser, PossibleValue, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::{Parser, ValueEnum};
 
 use cargo_wizard::{
-    PredefinedTemplateKind, Profile, WizardOptions, parse_workspace, resolve_manifest_path,
+    EnvFormat, KnownCargoOptions, PredefinedTemplateKind, Profile, Template, Toolchain,
+    WizardOptions, WorkspaceManifests, format_cargo_invocation, format_env_vars, parse_workspace,
+    resolve_manifest_path, template_config_args, template_env_vars,
 };
 
 use crate::cli::CliConfig;
 use crate::dialog::{
-    DialogError, KnownCargoOptions, on_template_applied, profile_from_str, run_root_dialog,
+    DialogError, PromptResult, on_template_applied, print_profile_audits, profile_from_str,
+    run_init_dialog, run_root_dialog,
 };
 
 mod cli;
@@ -39,7 +44,7 @@ enum ColorPolicy {
 
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum NightlyOptions {
-    /// Include nightly options if you invoke `cargo wizard` with a nightly compiler.
+    /// Include nightly options if the workspace is built with a nightly compiler.
     Auto,
     /// Include nightly options.
     On,
@@ -47,6 +52,14 @@ enum NightlyOptions {
     Off,
 }
 
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum EmitMode {
+    /// Modify Cargo.toml and the Cargo config file.
+    Files,
+    /// Print environment variables that configure Cargo, without modifying any files.
+    Env,
+}
+
 #[derive(clap::Parser, Debug)]
 struct InnerArgs {
     /// Console color policy.
@@ -74,6 +87,25 @@ struct InnerArgs {
     )]
     nightly: NightlyOptions,
 
+    /// Write all template items to custom profiles, even if they have the same value as the one
+    /// inherited from the parent profile.
+    #[arg(
+        long,
+        global = true,
+        help_heading("GLOBAL OPTIONS"),
+        hide_short_help(true)
+    )]
+    write_inherited_values: bool,
+
+    /// Convert `build.rustflags` in the Cargo config from a string into an array of strings.
+    #[arg(
+        long,
+        global = true,
+        help_heading("GLOBAL OPTIONS"),
+        hide_short_help(true)
+    )]
+    normalize_rustflags: bool,
+
     #[clap(subcommand)]
     subcmd: Option<SubCommand>,
 }
@@ -95,32 +127,188 @@ struct ApplyArgs {
     template: PredefinedTemplateKind,
     /// Cargo profile that should be created or modified.
     profile: ProfileArg,
+    /// Profile that a custom profile should inherit from.
+    /// If not specified, the base profile of the template (`dev` or `release`) will be used.
+    #[clap(long)]
+    inherits: Option<ProfileArg>,
+}
+
+impl ApplyArgs {
+    fn build_template(&self, options: &WizardOptions) -> anyhow::Result<Template> {
+        build_template(
+            self.template,
+            &self.profile.0,
+            self.inherits.as_ref().map(|p| &p.0),
+            options,
+        )
+    }
+}
+
+/// Positional argument of `apply`, which is either a template or a profile (depending on its
+/// position), or a `<template>=<profile>` pair.
+#[derive(Clone, Debug)]
+enum TargetArg {
+    Name(String),
+    Pair(PredefinedTemplateKind, Profile),
+}
+
+/// Parses [`TargetArg`]s, validating the templates and profiles of pairs.
+/// Template names are reported as possible values, so that they are listed in `--help`.
+#[derive(Clone)]
+struct TargetArgParser;
+
+impl TypedValueParser for TargetArgParser {
+    type Value = TargetArg;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let Some((template, profile)) = value.to_str().and_then(|value| value.split_once('='))
+        else {
+            let name = clap::builder::StringValueParser::new().parse_ref(cmd, arg, value)?;
+            return Ok(TargetArg::Name(name));
+        };
+        let template = EnumValueParser::<PredefinedTemplateKind>::new().parse_ref(
+            cmd,
+            arg,
+            OsStr::new(template),
+        )?;
+        let profile = profile_from_str(profile)
+            .map_err(|error| cmd.clone().error(ErrorKind::ValueValidation, error))?;
+        Ok(TargetArg::Pair(template, profile))
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            PredefinedTemplateKind::value_variants()
+                .iter()
+                .filter_map(|kind| kind.to_possible_value()),
+        ))
+    }
+}
+
+#[derive(clap::Parser, Debug)]
+struct ApplyTargetsArgs {
+    /// Template and Cargo profile (`<template> <profile>`), or one or more `<template>=<profile>`
+    /// pairs that will be applied together (e.g. `fast-compile=dev fast-runtime=release`).
+    #[clap(required = true, value_name = "TARGET", value_parser = TargetArgParser)]
+    targets: Vec<TargetArg>,
+    /// Profile that custom profiles should inherit from.
+    /// If not specified, the base profile of the template (`dev` or `release`) will be used.
+    #[clap(long)]
+    inherits: Option<ProfileArg>,
+}
+
+impl ApplyTargetsArgs {
+    /// Parses the targets into pairs of templates and profiles.
+    fn parse_targets(&self) -> anyhow::Result<Vec<(PredefinedTemplateKind, Profile)>> {
+        let pairs = match self.targets.as_slice() {
+            [TargetArg::Name(template), TargetArg::Name(profile)] => {
+                let template = PredefinedTemplateKind::from_str(template, false).map_err(|_| {
+                    anyhow::anyhow!(
+                        "Invalid template `{template}`, expected one of: {}",
+                        PredefinedTemplateKind::value_variants()
+                            .iter()
+                            .filter_map(|kind| kind.to_possible_value())
+                            .map(|value| value.get_name().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+                let profile = profile_from_str(profile).map_err(anyhow::Error::msg)?;
+                vec![(template, profile)]
+            }
+            targets => targets
+                .iter()
+                .map(|target| match target {
+                    TargetArg::Pair(template, profile) => Ok((*template, profile.clone())),
+                    TargetArg::Name(target) => Err(anyhow::anyhow!(
+                        "Invalid target `{target}`, expected `<template> <profile>` or `<template>=<profile>` pairs"
+                    )),
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        };
+
+        let mut targets: Vec<(PredefinedTemplateKind, Profile)> = vec![];
+        for (template, profile) in pairs {
+            if targets.iter().any(|(_, p)| *p == profile) {
+                return Err(anyhow::anyhow!(
+                    "Profile {} is specified multiple times",
+                    profile.name()
+                ));
+            }
+            targets.push((template, profile));
+        }
+        Ok(targets)
+    }
 }
 
 #[derive(clap::Parser, Debug)]
 enum SubCommand {
     /// Apply a predefined template to the selected profile.
     Apply {
+        #[clap(flatten)]
+        args: ApplyTargetsArgs,
+        /// Path to a Cargo.toml manifest.
+        /// If not specified, it will be resolved to the current Cargo workspace.
+        #[clap(long)]
+        manifest_path: Option<PathBuf>,
+        /// Path to a Cargo config file that should be modified.
+        /// If not specified, `.cargo/config.toml` (or the legacy `.cargo/config`) in the workspace
+        /// root will be used.
+        #[clap(long)]
+        config_path: Option<PathBuf>,
+        /// How the template should be applied.
+        #[clap(long, value_enum, default_value_t = EmitMode::Files)]
+        emit: EmitMode,
+        /// Format of the environment variables printed with `--emit env` [default: shell].
+        #[clap(long, value_enum)]
+        env_format: Option<EnvFormat>,
+    },
+    /// Print a `cargo` invocation that uses a predefined template for the selected profile
+    /// through `--config` arguments, without modifying any files.
+    Args {
         #[clap(flatten)]
         args: ApplyArgs,
         /// Path to a Cargo.toml manifest.
         /// If not specified, it will be resolved to the current Cargo workspace.
         #[clap(long)]
         manifest_path: Option<PathBuf>,
+        /// Cargo command that should be invoked.
+        #[clap(long, default_value = "build")]
+        command: String,
+        /// Run the printed `cargo` invocation in the workspace root.
+        #[clap(long)]
+        run: bool,
+    },
+    /// Set up the profiles of a new project (`dev`, `release` and optionally `min-size` and
+    /// `profiling`) based on a few questions.
+    Init {
+        /// Path to a Cargo.toml manifest.
+        /// If not specified, it will be resolved to the current Cargo workspace.
+        #[clap(long)]
+        manifest_path: Option<PathBuf>,
+    },
+    /// Report which template each profile of the workspace resembles, which of its settings
+    /// look counterproductive and which changes are suggested.
+    Audit {
+        /// Path to a Cargo.toml manifest.
+        /// If not specified, it will be resolved to the current Cargo workspace.
+        #[clap(long)]
+        manifest_path: Option<PathBuf>,
     },
 }
 
-fn options_from_args(args: &InnerArgs) -> WizardOptions {
+fn options_from_args(args: &InnerArgs, toolchain: Option<Toolchain>) -> WizardOptions {
     let mut options = WizardOptions::default();
     let is_nightly = match args.nightly {
-        NightlyOptions::Auto => match rustc_version::version_meta() {
-            Ok(metadata) => {
-                matches!(metadata.channel, Channel::Nightly)
-            }
-            Err(error) => {
-                eprintln!(
-                    "Cannot get compiler channel, defaulting to *no* nightly options ({error:?}"
-                );
+        NightlyOptions::Auto => match &toolchain {
+            Some(toolchain) => toolchain.is_nightly(),
+            None => {
+                eprintln!("Cannot get compiler channel, defaulting to *no* nightly options");
                 false
             }
         },
@@ -130,48 +318,203 @@ fn options_from_args(args: &InnerArgs) -> WizardOptions {
     if is_nightly {
         options = options.with_nightly_items();
     }
+    if let Some(toolchain) = toolchain {
+        options = options.with_toolchain(toolchain);
+    }
+    if args.write_inherited_values {
+        options = options.with_inherited_values_written();
+    }
+    if args.normalize_rustflags {
+        options = options.with_normalized_rustflags();
+    }
+    if let Some(SubCommand::Apply {
+        config_path: Some(path),
+        ..
+    }) = &args.subcmd
+    {
+        options = options.with_config_path(path.clone());
+    }
     options
 }
 
+/// Makes sure that the workspace root manifest will be modified, because Cargo ignores profiles
+/// in the manifests of workspace members. Also warns about such ignored profiles.
+fn check_workspace_manifests(manifest_path: PathBuf) -> PathBuf {
+    let manifests = match WorkspaceManifests::resolve(&manifest_path) {
+        Ok(manifests) => manifests,
+        Err(error) => {
+            eprintln!(
+                "Cannot resolve the workspace of {} ({error:?})",
+                manifest_path.display()
+            );
+            return manifest_path;
+        }
+    };
+
+    match manifests.members_with_ignored_profiles() {
+        Ok(members) => {
+            for member in members {
+                eprintln!(
+                    "⚠️  The [profile] section in {} is ignored by Cargo, because it is not the root manifest of the workspace ({}).",
+                    member.display(),
+                    manifests.root_manifest().display()
+                );
+            }
+        }
+        Err(error) => {
+            eprintln!("Cannot check profiles of workspace members ({error:?})");
+        }
+    }
+
+    if manifests.is_root_manifest(&manifest_path) {
+        manifest_path
+    } else {
+        eprintln!(
+            "⚠️  {} is a member of a workspace and Cargo would ignore profiles defined in it. Using the workspace root manifest {} instead.",
+            manifest_path.display(),
+            manifests.root_manifest().display()
+        );
+        manifests.root_manifest().to_path_buf()
+    }
+}
+
+/// Detects the toolchain used by the workspace located in the given directory.
+fn detect_toolchain(workspace_root: &Path) -> Option<Toolchain> {
+    match Toolchain::detect(workspace_root) {
+        Ok(toolchain) => Some(toolchain),
+        Err(error) => {
+            eprintln!("Cannot detect the compiler toolchain ({error:?})");
+            None
+        }
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     match args {
         Args::Wizard(root_args) => {
-            let options = options_from_args(&root_args);
-            let cargo_options =
-                KnownCargoOptions::create().context("Cannot get known Cargo options")?;
-            let cli_config = setup_cli(root_args.colors);
+            let cli_config = setup_cli(root_args.colors.clone());
+            let manifest_path = match &root_args.subcmd {
+                Some(SubCommand::Apply {
+                    manifest_path: Some(path),
+                    ..
+                })
+                | Some(SubCommand::Args {
+                    manifest_path: Some(path),
+                    ..
+                })
+                | Some(SubCommand::Init {
+                    manifest_path: Some(path),
+                })
+                | Some(SubCommand::Audit {
+                    manifest_path: Some(path),
+                }) => path.clone(),
+                _ => resolve_manifest_path().context("Cannot resolve Cargo.toml path")?,
+            };
+            let manifest_path = check_workspace_manifests(manifest_path);
+            let workspace_root = manifest_path.parent().expect("Manifest path has no parent");
+            let workspace = parse_workspace(&manifest_path)?;
+            let mut options = options_from_args(&root_args, detect_toolchain(workspace_root));
+            if let Some(rust_version) = workspace.rust_version() {
+                options = options.with_rust_version(rust_version);
+            }
+            let cargo_options = KnownCargoOptions::create(workspace_root, &options)
+                .context("Cannot get known Cargo options")?;
             match root_args.subcmd {
                 Some(SubCommand::Apply {
                     args,
-                    manifest_path,
+                    emit,
+                    env_format,
+                    ..
                 }) => {
-                    let manifest_path = match manifest_path {
-                        Some(path) => path,
-                        None => {
-                            resolve_manifest_path().context("Cannot resolve Cargo.toml path")?
-                        }
-                    };
-                    let workspace = parse_workspace(&manifest_path)?;
-                    let template = args.template.build_template(&options);
-                    let modified = workspace.apply_template(&args.profile.0, &template)?;
+                    let targets = args
+                        .parse_targets()?
+                        .into_iter()
+                        .map(|(kind, profile)| {
+                            let template = build_template(
+                                kind,
+                                &profile,
+                                args.inherits.as_ref().map(|p| &p.0),
+                                &options,
+                            )?;
+                            cargo_options.validate_template(&template)?;
+                            Ok((kind, profile, template))
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    if env_format.is_some() && !matches!(emit, EmitMode::Env) {
+                        return Err(anyhow::anyhow!("`--env-format` requires `--emit env`"));
+                    }
+                    if let EmitMode::Env = emit {
+                        let [(_, profile, template)] = targets.as_slice() else {
+                            return Err(anyhow::anyhow!(
+                                "`--emit env` supports only a single template and profile"
+                            ));
+                        };
+                        let vars = template_env_vars(profile, template, &options)?;
+                        print!(
+                            "{}",
+                            format_env_vars(&vars, env_format.unwrap_or(EnvFormat::Shell))
+                        );
+                        return Ok(());
+                    }
+                    let templates: Vec<_> = targets
+                        .iter()
+                        .map(|(_, profile, template)| (profile, template))
+                        .collect();
+                    let modified = workspace.apply_templates(&templates, &options)?;
+                    let rustflags_overrides = modified.rustflags_overrides().to_vec();
                     modified.write()?;
-                    on_template_applied(&cargo_options, args.template, &template, &args.profile.0);
+                    for (index, (kind, profile, template)) in targets.iter().enumerate() {
+                        // Print the overridden rustflags only once, they are shared by all profiles
+                        let overrides = if index + 1 == targets.len() {
+                            rustflags_overrides.as_slice()
+                        } else {
+                            &[]
+                        };
+                        on_template_applied(
+                            &cargo_options,
+                            *kind,
+                            template,
+                            profile,
+                            &options,
+                            overrides,
+                        );
+                    }
                 }
-                None => {
-                    if let Err(error) = run_root_dialog(cli_config, cargo_options, options) {
-                        match error {
-                            DialogError::Interrupted => {
-                                // Print an empty line when the app is interrupted, to avoid
-                                // overwriting the last line.
-                                println!();
-                            }
-                            DialogError::Generic(error) => {
-                                panic!("{error:?}");
-                            }
-                        }
+                Some(SubCommand::Args {
+                    args, command, run, ..
+                }) => {
+                    let template = args.build_template(&options)?;
+                    cargo_options.validate_template(&template)?;
+                    let config_args = template_config_args(&args.profile.0, &template, &options);
+                    println!(
+                        "{}",
+                        format_cargo_invocation(&command, &args.profile.0, &config_args)
+                    );
+                    if run {
+                        run_cargo(workspace_root, &command, &args.profile.0, &config_args)?;
                     }
                 }
+                Some(SubCommand::Audit { .. }) => {
+                    let audits = workspace.audit_profiles(&options)?;
+                    print_profile_audits(&cargo_options, &audits);
+                }
+                Some(SubCommand::Init { .. }) => {
+                    handle_dialog_result(run_init_dialog(
+                        cli_config,
+                        cargo_options,
+                        options,
+                        workspace,
+                    ));
+                }
+                None => {
+                    handle_dialog_result(run_root_dialog(
+                        cli_config,
+                        cargo_options,
+                        options,
+                        workspace,
+                    ));
+                }
             }
         }
     }
@@ -179,6 +522,67 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+fn handle_dialog_result(result: PromptResult<()>) {
+    if let Err(error) = result {
+        match error {
+            DialogError::Interrupted => {
+                // Print an empty line when the app is interrupted, to avoid
+                // overwriting the last line.
+                println!();
+            }
+            DialogError::Generic(error) => {
+                panic!("{error:?}");
+            }
+        }
+    }
+}
+
+/// Builds the predefined template for the given profile, optionally inheriting from another
+/// profile.
+fn build_template(
+    kind: PredefinedTemplateKind,
+    profile: &Profile,
+    inherits: Option<&Profile>,
+    options: &WizardOptions,
+) -> anyhow::Result<Template> {
+    let mut template = kind.build_template(options);
+    if let Some(inherits) = inherits {
+        if profile.is_builtin() {
+            return Err(anyhow::anyhow!(
+                "Built-in profile {} cannot inherit from other profiles",
+                profile.name()
+            ));
+        }
+        template.set_inherits(inherits.clone());
+    }
+    Ok(template)
+}
+
+/// Runs `cargo <command>` with the given profile and `--config` arguments, and exits with its exit
+/// code if it fails.
+fn run_cargo(
+    workspace_root: &Path,
+    command: &str,
+    profile: &Profile,
+    config_args: &[String],
+) -> anyhow::Result<()> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut cmd = std::process::Command::new(cargo);
+    cmd.current_dir(workspace_root)
+        .arg(command)
+        .args(["--profile", profile.name()]);
+    for arg in config_args {
+        cmd.arg("--config").arg(arg);
+    }
+    let status = cmd
+        .status()
+        .with_context(|| format!("Cannot run `cargo {command}`"))?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
 fn setup_cli(policy: ColorPolicy) -> CliConfig {
     let mut use_colors = match policy {
         ColorPolicy::Always => true,
@@ -1,9 +1,9 @@
 use rustc_version::Version;
 
-use crate::template::{TemplateItemId, dev_profile, release_profile};
+use crate::template::{TemplateBuilder, TemplateItemId, dev_profile, release_profile};
 use crate::toml::TomlValue;
 use crate::utils::get_core_count;
-use crate::{Template, WizardOptions};
+use crate::{BuiltinProfile, Template, WizardOptions};
 
 /// Enumeration of predefined templates.
 #[derive(clap::ValueEnum, Clone, Copy, Debug)]
@@ -14,6 +14,8 @@ pub enum PredefinedTemplateKind {
     FastRuntime,
     /// Profile designed for minimal binary size.
     MinSize,
+    /// Profile designed for profiling optimized builds (e.g. with `perf` or `samply`).
+    Profiling,
 }
 
 impl PredefinedTemplateKind {
@@ -22,6 +24,7 @@ impl PredefinedTemplateKind {
             PredefinedTemplateKind::FastCompile => fast_compile_template(options),
             PredefinedTemplateKind::FastRuntime => fast_runtime_template(),
             PredefinedTemplateKind::MinSize => min_size_template(),
+            PredefinedTemplateKind::Profiling => profiling_template(options),
         }
     }
 }
@@ -45,17 +48,16 @@ pub fn fast_compile_template(options: &WizardOptions) -> Template {
     let mut builder = dev_profile().item(TemplateItemId::DebugInfo, TomlValue::int(0));
 
     #[cfg(unix)]
-    match rustc_version::version_meta() {
-        Ok(meta) => {
-            if should_suggest_lld(&meta.semver, &meta.host) {
+    match options.toolchain() {
+        Some(toolchain) => {
+            if should_suggest_lld(toolchain.version(), toolchain.host()) {
                 builder = builder.item(TemplateItemId::Linker, TomlValue::string("lld"));
             }
         }
-        Err(error) => {
+        None => {
             if !cfg!(target_os = "macos") {
                 builder = builder.item(TemplateItemId::Linker, TomlValue::string("lld"));
             }
-            eprintln!("Cannot get compiler version metadata. ({error:?})");
         }
     }
 
@@ -98,14 +100,54 @@ pub fn min_size_template() -> Template {
         .build()
 }
 
+/// Template that keeps the runtime performance of release builds, but with the debuginfo and
+/// frame pointers needed by profilers.
+pub fn profiling_template(options: &WizardOptions) -> Template {
+    // `line-tables-only` is enough for symbolized stack traces and much smaller than full debuginfo
+    let debuginfo = if options.check_version(&Version::new(1, 71, 0)).is_ok() {
+        TomlValue::string("line-tables-only")
+    } else {
+        TomlValue::bool(true)
+    };
+    let split_debuginfo =
+        profiling_split_debuginfo(options.toolchain().map(|toolchain| toolchain.host()));
+
+    // Only the debugging-related items are set, everything else is inherited from the profile
+    // that is being profiled (`release` by default)
+    TemplateBuilder::new(BuiltinProfile::Release)
+        .item(TemplateItemId::DebugInfo, debuginfo)
+        .item(TemplateItemId::Strip, TomlValue::bool(false))
+        .item(
+            TemplateItemId::SplitDebugInfo,
+            TomlValue::string(split_debuginfo),
+        )
+        .item(TemplateItemId::ForceFramePointers, TomlValue::bool(true))
+        .build()
+}
+
+/// Keeps debuginfo where profilers look for it by default on the given host.
+fn profiling_split_debuginfo(host: Option<&str>) -> &'static str {
+    match host {
+        Some(host) if host.ends_with("-apple-darwin") => "unpacked",
+        Some(host) if host.contains("-windows-") => "packed",
+        Some(_) => "off",
+        None if cfg!(target_os = "macos") => "unpacked",
+        None if cfg!(windows) => "packed",
+        None => "off",
+    }
+}
+
 /// Test that the predefined templates can be created without panicking.
 #[cfg(test)]
 mod tests {
-    use crate::{WizardOptions, fast_compile_template, fast_runtime_template, min_size_template};
+    use crate::{
+        TemplateItemId, TomlValue, WizardOptions, fast_compile_template, fast_runtime_template,
+        min_size_template, profiling_template,
+    };
 
+    use super::profiling_split_debuginfo;
     #[cfg(unix)]
     use super::should_suggest_lld;
-    #[cfg(unix)]
     use rustc_version::Version;
 
     #[test]
@@ -123,6 +165,43 @@ mod tests {
         min_size_template();
     }
 
+    #[test]
+    fn create_profiling_template() {
+        profiling_template(&WizardOptions::default());
+    }
+
+    #[test]
+    fn profiling_debuginfo_respects_rust_version() {
+        let template = profiling_template(&WizardOptions::default());
+        assert_eq!(
+            template.get_item(&TemplateItemId::DebugInfo),
+            Some(&TomlValue::string("line-tables-only"))
+        );
+
+        let options = WizardOptions::default().with_rust_version(Version::new(1, 70, 0));
+        let template = profiling_template(&options);
+        assert_eq!(
+            template.get_item(&TemplateItemId::DebugInfo),
+            Some(&TomlValue::bool(true))
+        );
+    }
+
+    #[test]
+    fn profiling_split_debuginfo_matches_hosts() {
+        assert_eq!(
+            profiling_split_debuginfo(Some("x86_64-unknown-linux-gnu")),
+            "off"
+        );
+        assert_eq!(
+            profiling_split_debuginfo(Some("aarch64-apple-darwin")),
+            "unpacked"
+        );
+        assert_eq!(
+            profiling_split_debuginfo(Some("x86_64-pc-windows-msvc")),
+            "packed"
+        );
+    }
+
     #[test]
     #[cfg(unix)]
     fn fast_compile_linker_matrix_matches_supported_hosts() {
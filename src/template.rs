@@ -1,35 +1,126 @@
-use indexmap::IndexMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use indexmap::{IndexMap, IndexSet};
+use rustc_version::Version;
+
+use crate::ItemRegistry;
+use crate::items::builtin_default_values;
 use crate::toml::TomlValue;
-use crate::workspace::manifest::BuiltinProfile;
+use crate::toolchain::Toolchain;
+use crate::workspace::manifest::{BuiltinProfile, Profile};
 
 /// A set of Cargo profile items and .cargo/config.toml config items that can be applied to a
 /// Cargo workspace.
 #[derive(Debug)]
 pub struct Template {
-    inherits: BuiltinProfile,
+    inherits: Profile,
     items: IndexMap<TemplateItemId, TomlValue>,
+    /// Items that were explicitly removed from the template.
+    /// Their existing values will be removed when the template is applied.
+    removed_items: IndexSet<TemplateItemId>,
 }
 
 impl Template {
-    pub fn inherits(&self) -> BuiltinProfile {
-        self.inherits
+    /// Profile that custom profiles created from this template will inherit from.
+    pub fn inherits(&self) -> &Profile {
+        &self.inherits
+    }
+
+    pub fn set_inherits(&mut self, profile: Profile) {
+        self.inherits = profile;
     }
 
-    pub fn iter_items(&self) -> impl Iterator<Item = (TemplateItemId, &TomlValue)> {
-        self.items.iter().map(|(id, value)| (*id, value))
+    pub fn iter_items(&self) -> impl Iterator<Item = (&TemplateItemId, &TomlValue)> {
+        self.items.iter()
     }
 
-    pub fn get_item(&self, id: TemplateItemId) -> Option<&TomlValue> {
-        self.items.get(&id)
+    pub fn get_item(&self, id: &TemplateItemId) -> Option<&TomlValue> {
+        self.items.get(id)
     }
 
     pub fn insert_item(&mut self, id: TemplateItemId, value: TomlValue) {
+        self.removed_items.shift_remove(&id);
         self.items.insert(id, value);
     }
 
+    /// Removes the item from the template. When the template is applied, the existing value of
+    /// the item will be removed from the profile or the config.
     pub fn remove_item(&mut self, id: TemplateItemId) {
         self.items.shift_remove(&id);
+        self.removed_items.insert(id);
+    }
+
+    /// Drops the item from the template without removing its existing value, so that the
+    /// template does not affect the item at all.
+    pub fn omit_item(&mut self, id: &TemplateItemId) {
+        self.items.shift_remove(id);
+        self.removed_items.shift_remove(id);
+    }
+
+    pub fn iter_removed_items(&self) -> impl Iterator<Item = &TemplateItemId> {
+        self.removed_items.iter()
+    }
+
+    /// Keeps only items that are stored in Cargo profiles. Rustflags and config keys are shared by
+    /// all profiles, so they can be dropped from templates that should only affect their profile.
+    pub fn retain_profile_items(&mut self, registry: &ItemRegistry) {
+        self.items
+            .retain(|id, _| registry.profile_key(id).is_some());
+    }
+}
+
+/// Version of the serialized form of templates.
+/// It is increased whenever the format changes, so that templates saved by older releases of
+/// cargo-wizard can still be read.
+#[cfg(feature = "serde")]
+pub const TEMPLATE_FORMAT_VERSION: u32 = 1;
+
+#[cfg(feature = "serde")]
+mod serialization {
+    use indexmap::{IndexMap, IndexSet};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{TEMPLATE_FORMAT_VERSION, Template, TemplateItemId};
+    use crate::{Profile, TomlValue};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct SerializedTemplate {
+        format_version: u32,
+        inherits: String,
+        items: IndexMap<TemplateItemId, TomlValue>,
+        #[serde(default, skip_serializing_if = "IndexSet::is_empty")]
+        removed_items: IndexSet<TemplateItemId>,
+    }
+
+    impl Serialize for Template {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            SerializedTemplate {
+                format_version: TEMPLATE_FORMAT_VERSION,
+                inherits: self.inherits.name().to_string(),
+                items: self.items.clone(),
+                removed_items: self.removed_items.clone(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Template {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let template = SerializedTemplate::deserialize(deserializer)?;
+            if template.format_version > TEMPLATE_FORMAT_VERSION {
+                return Err(serde::de::Error::custom(format!(
+                    "unsupported template format version {}, the newest supported version is {TEMPLATE_FORMAT_VERSION}",
+                    template.format_version
+                )));
+            }
+            Ok(Template {
+                inherits: Profile::from_name(&template.inherits),
+                items: template.items,
+                removed_items: template.removed_items,
+            })
+        }
     }
 }
 
@@ -55,57 +146,132 @@ impl TemplateBuilder {
     pub fn build(self) -> Template {
         let TemplateBuilder { inherits, profile } = self;
         Template {
-            inherits,
+            inherits: Profile::Builtin(inherits),
             items: profile,
+            removed_items: Default::default(),
         }
     }
 }
 
 /// Default properties of the dev profile.
 pub fn dev_profile() -> TemplateBuilder {
-    TemplateBuilder::new(BuiltinProfile::Dev)
-        .item(TemplateItemId::OptimizationLevel, TomlValue::Int(0))
-        .item(TemplateItemId::DebugInfo, TomlValue::Bool(true))
-        .item(TemplateItemId::Strip, TomlValue::String("none".to_string()))
-        .item(TemplateItemId::Lto, TomlValue::Bool(false))
-        .item(TemplateItemId::CodegenUnits, TomlValue::Int(256))
-        .item(TemplateItemId::Incremental, TomlValue::Bool(true))
+    builtin_profile(BuiltinProfile::Dev)
 }
 
 /// Default properties of the release profile.
 pub fn release_profile() -> TemplateBuilder {
-    TemplateBuilder::new(BuiltinProfile::Release)
-        .item(TemplateItemId::OptimizationLevel, TomlValue::Int(3))
-        .item(TemplateItemId::DebugInfo, TomlValue::Bool(false))
-        .item(TemplateItemId::Strip, TomlValue::String("none".to_string()))
-        .item(TemplateItemId::Lto, TomlValue::Bool(false))
-        .item(TemplateItemId::CodegenUnits, TomlValue::Int(16))
-        .item(TemplateItemId::Incremental, TomlValue::Bool(false))
+    builtin_profile(BuiltinProfile::Release)
+}
+
+/// Order of the items with default values in templates based on the built-in profiles. The
+/// default values themselves are described by the built-in items.
+const DEFAULT_PROFILE_ITEMS: [TemplateItemId; 6] = [
+    TemplateItemId::OptimizationLevel,
+    TemplateItemId::DebugInfo,
+    TemplateItemId::Strip,
+    TemplateItemId::Lto,
+    TemplateItemId::CodegenUnits,
+    TemplateItemId::Incremental,
+];
+
+fn builtin_profile(profile: BuiltinProfile) -> TemplateBuilder {
+    let mut defaults = builtin_default_values(profile);
+    defaults.sort_by_key(|(id, _)| {
+        DEFAULT_PROFILE_ITEMS
+            .iter()
+            .position(|item| item == id)
+            .unwrap_or(DEFAULT_PROFILE_ITEMS.len())
+    });
+    defaults
+        .into_iter()
+        .fold(TemplateBuilder::new(profile), |builder, (id, value)| {
+            builder.item(id, value)
+        })
 }
 
 /// Identifier of a specific item of a template.
-#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
+///
+/// When serialized, items of Cargo profiles use the names of the corresponding Cargo keys.
+#[derive(Debug, Hash, Eq, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum TemplateItemId {
-    // Do not forget to modify CargoKnownOptions when adding new variants to this enum
+    // Do not forget to add an entry to the built-in items in `items.rs` when adding new built-in
+    // variants to this enum
+    #[cfg_attr(feature = "serde", serde(rename = "debug"))]
     DebugInfo,
+    #[cfg_attr(feature = "serde", serde(rename = "split-debuginfo"))]
     SplitDebugInfo,
     Strip,
     Lto,
     CodegenUnits,
     Panic,
+    #[cfg_attr(feature = "serde", serde(rename = "opt-level"))]
     OptimizationLevel,
     Incremental,
     CodegenBackend,
     FrontendThreads,
+    #[cfg_attr(feature = "serde", serde(rename = "target-cpu"))]
     TargetCpuInstructionSet,
     Linker,
+    ForceFramePointers,
+    RelocationModel,
+    EmbedBitcode,
+    ShareGenerics,
+    LocationDetail,
+    FmtDebug,
+    /// Crates of the standard library that should be rebuilt, separated by commas.
+    BuildStd,
+    /// Features of the standard library used when it is rebuilt, separated by commas.
+    BuildStdFeatures,
+    /// Target triple used for builds by default.
+    BuildTarget,
+    /// Additional flags passed to `rustc`, separated by whitespace.
+    ExtraRustflags,
+    /// Item registered in an [`ItemRegistry`](crate::ItemRegistry) by users of the library.
+    /// It is serialized using its ID, so it should not share the name of a built-in item.
+    #[cfg_attr(feature = "serde", serde(untagged))]
+    Custom(CustomItemId),
+}
+
+/// Unique identifier of a custom template item (e.g. `overflow-checks`).
+#[derive(Debug, Hash, Eq, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct CustomItemId(pub String);
+
+impl CustomItemId {
+    pub fn new(id: &str) -> Self {
+        Self(id.to_string())
+    }
 }
 
 /// Describes options for applying templates
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct WizardOptions {
     /// Include template items that require a nightly compiler.
     nightly_items: bool,
+    /// Toolchain detected for the workspace.
+    toolchain: Option<Toolchain>,
+    /// `rust-version` of the workspace.
+    rust_version: Option<Version>,
+    /// Pin a nightly toolchain in `rust-toolchain.toml` when applying a template.
+    pin_nightly_toolchain: bool,
+    /// Write all template items to custom profiles, even if they have the same value as the one
+    /// inherited from the parent profile.
+    write_inherited_values: bool,
+    /// Cargo config file that should be modified instead of the config in the workspace root.
+    config_path: Option<PathBuf>,
+    /// Convert `build.rustflags` written as a string into an array of strings.
+    normalize_rustflags: bool,
+    /// Items that can be used in templates.
+    item_registry: Arc<ItemRegistry>,
 }
 
 impl WizardOptions {
@@ -117,4 +283,218 @@ impl WizardOptions {
         self.nightly_items = true;
         self
     }
+
+    pub fn toolchain(&self) -> Option<&Toolchain> {
+        self.toolchain.as_ref()
+    }
+
+    pub fn with_toolchain(mut self, toolchain: Toolchain) -> Self {
+        self.toolchain = Some(toolchain);
+        self
+    }
+
+    pub fn rust_version(&self) -> Option<&Version> {
+        self.rust_version.as_ref()
+    }
+
+    pub fn with_rust_version(mut self, version: Version) -> Self {
+        self.rust_version = Some(version);
+        self
+    }
+
+    /// Checks if options that require the given Cargo version can be used with the detected
+    /// toolchain and the `rust-version` of the workspace.
+    pub fn check_version(&self, required: &Version) -> Result<(), String> {
+        if let Some(toolchain) = &self.toolchain {
+            // Nightly and beta toolchains already support the options stabilized in their version
+            let version = toolchain.version();
+            let version = Version::new(version.major, version.minor, version.patch);
+            if &version < required {
+                return Err(format!("the detected toolchain is {version}"));
+            }
+        }
+        if let Some(version) = &self.rust_version
+            && version < required
+        {
+            return Err(format!("the workspace `rust-version` is {version}"));
+        }
+        Ok(())
+    }
+
+    pub fn pin_nightly_toolchain_enabled(&self) -> bool {
+        self.pin_nightly_toolchain
+    }
+
+    pub fn with_pinned_nightly_toolchain(mut self) -> Self {
+        self.pin_nightly_toolchain = true;
+        self
+    }
+
+    pub fn write_inherited_values_enabled(&self) -> bool {
+        self.write_inherited_values
+    }
+
+    pub fn with_inherited_values_written(mut self) -> Self {
+        self.write_inherited_values = true;
+        self
+    }
+
+    pub fn config_path(&self) -> Option<&Path> {
+        self.config_path.as_deref()
+    }
+
+    pub fn with_config_path(mut self, path: PathBuf) -> Self {
+        self.config_path = Some(path);
+        self
+    }
+
+    pub fn normalize_rustflags_enabled(&self) -> bool {
+        self.normalize_rustflags
+    }
+
+    pub fn with_normalized_rustflags(mut self) -> Self {
+        self.normalize_rustflags = true;
+        self
+    }
+
+    pub fn item_registry(&self) -> &ItemRegistry {
+        &self.item_registry
+    }
+
+    pub(crate) fn item_registry_arc(&self) -> Arc<ItemRegistry> {
+        self.item_registry.clone()
+    }
+
+    /// Uses the given registry instead of the built-in items, e.g. to add custom items.
+    pub fn with_item_registry(mut self, registry: ItemRegistry) -> Self {
+        self.item_registry = Arc::new(registry);
+        self
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::{
+        BuiltinProfile, CustomItemId, ItemRegistry, Profile, Template, TemplateItemId, TomlValue,
+        fast_runtime_template,
+    };
+
+    use super::TemplateBuilder;
+
+    #[test]
+    fn serialize_template() {
+        let mut template = fast_runtime_template();
+        template.set_inherits(Profile::Custom("dist".to_string()));
+        template.remove_item(TemplateItemId::Panic);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&template).unwrap(), @r###"
+        {
+          "format-version": 1,
+          "inherits": "dist",
+          "items": {
+            "opt-level": 3,
+            "debug": false,
+            "strip": "none",
+            "lto": true,
+            "codegen-units": 1,
+            "incremental": false,
+            "target-cpu": "native"
+          },
+          "removed-items": [
+            "panic"
+          ]
+        }
+        "###);
+    }
+
+    #[test]
+    fn deserialize_template() {
+        let template: Template = serde_json::from_str(
+            r#"{
+    "format-version": 1,
+    "inherits": "release",
+    "items": { "opt-level": "s", "lto": true, "codegen-units": 1 }
+}"#,
+        )
+        .unwrap();
+        assert_eq!(template.inherits(), &Profile::release());
+        assert_eq!(
+            template.iter_items().collect::<Vec<_>>(),
+            vec![
+                (&TemplateItemId::OptimizationLevel, &TomlValue::string("s")),
+                (&TemplateItemId::Lto, &TomlValue::bool(true)),
+                (&TemplateItemId::CodegenUnits, &TomlValue::int(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_custom_items() {
+        let mut template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::Custom(CustomItemId::new("overflow-checks")),
+                TomlValue::bool(true),
+            )
+            .item(TemplateItemId::Lto, TomlValue::bool(true))
+            .build();
+        template.remove_item(TemplateItemId::Custom(CustomItemId::new("rpath")));
+        let serialized = serde_json::to_string_pretty(&template).unwrap();
+        insta::assert_snapshot!(serialized, @r###"
+        {
+          "format-version": 1,
+          "inherits": "release",
+          "items": {
+            "overflow-checks": true,
+            "lto": true
+          },
+          "removed-items": [
+            "rpath"
+          ]
+        }
+        "###);
+
+        let template: Template = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(
+            template.iter_items().collect::<Vec<_>>(),
+            vec![
+                (
+                    &TemplateItemId::Custom(CustomItemId::new("overflow-checks")),
+                    &TomlValue::bool(true)
+                ),
+                (&TemplateItemId::Lto, &TomlValue::bool(true)),
+            ]
+        );
+        assert_eq!(
+            template.iter_removed_items().collect::<Vec<_>>(),
+            vec![&TemplateItemId::Custom(CustomItemId::new("rpath"))]
+        );
+    }
+
+    #[test]
+    fn reject_newer_format_version() {
+        let error = serde_json::from_str::<Template>(
+            r#"{ "format-version": 2, "inherits": "dev", "items": {} }"#,
+        )
+        .unwrap_err();
+        insta::assert_snapshot!(error.to_string(), @r###"
+        unsupported template format version 2, the newest supported version is 1
+        "###);
+    }
+
+    #[test]
+    fn profile_item_ids_match_cargo_keys() {
+        for id in [
+            TemplateItemId::DebugInfo,
+            TemplateItemId::SplitDebugInfo,
+            TemplateItemId::Strip,
+            TemplateItemId::Lto,
+            TemplateItemId::CodegenUnits,
+            TemplateItemId::Panic,
+            TemplateItemId::OptimizationLevel,
+            TemplateItemId::Incremental,
+            TemplateItemId::CodegenBackend,
+        ] {
+            let name = serde_json::to_value(&id).unwrap();
+            assert_eq!(name.as_str(), ItemRegistry::builtin().profile_key(&id));
+        }
+    }
 }
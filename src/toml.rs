@@ -1,11 +1,19 @@
-use toml_edit::Formatted;
+use indexmap::IndexMap;
+use toml_edit::{Array, Formatted, InlineTable, Item};
 
-/// Representation of a numeric, boolean or a string TOML value.
+/// Representation of a numeric, boolean, string, array or (inline) table TOML value.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
 pub enum TomlValue {
     Int(i64),
     Bool(bool),
     String(String),
+    Array(Vec<TomlValue>),
+    Table(IndexMap<String, TomlValue>),
 }
 
 impl TomlValue {
@@ -21,11 +29,123 @@ impl TomlValue {
         TomlValue::String(value.to_string())
     }
 
+    pub fn array(values: Vec<TomlValue>) -> Self {
+        TomlValue::Array(values)
+    }
+
+    pub fn table<'a>(entries: impl IntoIterator<Item = (&'a str, TomlValue)>) -> Self {
+        TomlValue::Table(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
     pub fn to_toml_value(&self) -> toml_edit::Value {
         match self {
             TomlValue::Int(value) => toml_edit::Value::Integer(Formatted::new(*value)),
             TomlValue::Bool(value) => toml_edit::Value::Boolean(Formatted::new(*value)),
             TomlValue::String(value) => toml_edit::Value::String(Formatted::new(value.clone())),
+            TomlValue::Array(values) => {
+                toml_edit::Value::Array(Array::from_iter(values.iter().map(|v| v.to_toml_value())))
+            }
+            TomlValue::Table(entries) => toml_edit::Value::InlineTable(InlineTable::from_iter(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.to_toml_value())),
+            )),
         }
     }
+
+    /// Converts a TOML value into a `TomlValue`.
+    /// Returns `None` if the value (or any of its nested values) has an unsupported type, such as
+    /// a float or a datetime.
+    pub fn from_toml_value(value: &toml_edit::Value) -> Option<Self> {
+        match value {
+            toml_edit::Value::Integer(value) => Some(TomlValue::Int(*value.value())),
+            toml_edit::Value::Boolean(value) => Some(TomlValue::Bool(*value.value())),
+            toml_edit::Value::String(value) => Some(TomlValue::String(value.value().clone())),
+            toml_edit::Value::Array(array) => array
+                .iter()
+                .map(Self::from_toml_value)
+                .collect::<Option<_>>()
+                .map(TomlValue::Array),
+            toml_edit::Value::InlineTable(table) => table
+                .iter()
+                .map(|(key, value)| Some((key.to_string(), Self::from_toml_value(value)?)))
+                .collect::<Option<_>>()
+                .map(TomlValue::Table),
+            toml_edit::Value::Float(_) | toml_edit::Value::Datetime(_) => None,
+        }
+    }
+
+    /// Converts a TOML item into a `TomlValue`. Standard tables (e.g. `[profile.dev.package.foo]`)
+    /// are converted into tables, just like inline tables.
+    pub(crate) fn from_toml_item(item: &Item) -> Option<Self> {
+        match item {
+            Item::Value(value) => Self::from_toml_value(value),
+            Item::Table(table) => table
+                .iter()
+                .map(|(key, item)| Some((key.to_string(), Self::from_toml_item(item)?)))
+                .collect::<Option<_>>()
+                .map(TomlValue::Table),
+            Item::None | Item::ArrayOfTables(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TomlValue;
+
+    #[test]
+    fn array_to_toml() {
+        let value = TomlValue::array(vec![TomlValue::string("a"), TomlValue::int(1)]);
+        insta::assert_snapshot!(value.to_toml_value().to_string(), @r###"["a", 1]"###);
+    }
+
+    #[test]
+    fn table_to_toml() {
+        let value = TomlValue::table([
+            ("opt-level", TomlValue::int(3)),
+            ("debug", TomlValue::bool(false)),
+        ]);
+        insta::assert_snapshot!(value.to_toml_value().to_string(), @"{ opt-level = 3, debug = false }");
+    }
+
+    #[test]
+    fn roundtrip_nested_value() {
+        let value = TomlValue::table([(
+            "foo",
+            TomlValue::array(vec![TomlValue::table([("bar", TomlValue::string("baz"))])]),
+        )]);
+        assert_eq!(
+            TomlValue::from_toml_value(&value.to_toml_value()),
+            Some(value)
+        );
+    }
+
+    #[test]
+    fn parse_standard_table() {
+        let document: toml_edit::DocumentMut = r#"
+[profile.dev.package.foo]
+opt-level = 3
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            TomlValue::from_toml_item(&document["profile"]["dev"]["package"]),
+            Some(TomlValue::table([(
+                "foo",
+                TomlValue::table([("opt-level", TomlValue::int(3))])
+            )]))
+        );
+    }
+
+    #[test]
+    fn unsupported_nested_value() {
+        let value: toml_edit::Value = "[1, 2.5]".parse().unwrap();
+        assert_eq!(TomlValue::from_toml_value(&value), None);
+    }
 }
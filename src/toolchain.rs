@@ -0,0 +1,118 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use rustc_version::{Channel, Version, VersionMeta};
+
+use crate::WizardResult;
+
+/// Rust toolchain that is used to build a Cargo workspace.
+#[derive(Clone, Debug)]
+pub struct Toolchain {
+    meta: VersionMeta,
+    toolchain_file: Option<PathBuf>,
+}
+
+impl Toolchain {
+    /// Detects the toolchain by running `rustc` from the root directory of a workspace, so that
+    /// `RUSTC` and `rust-toolchain(.toml)` files are taken into account.
+    pub fn detect(workspace_root: &Path) -> WizardResult<Self> {
+        let meta = VersionMeta::for_command(rustc_command(workspace_root))?;
+        Ok(Self {
+            meta,
+            toolchain_file: find_toolchain_file(workspace_root),
+        })
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.meta.semver
+    }
+
+    pub fn channel(&self) -> Channel {
+        self.meta.channel
+    }
+
+    pub fn channel_name(&self) -> &'static str {
+        match self.meta.channel {
+            Channel::Dev => "dev",
+            Channel::Nightly => "nightly",
+            Channel::Beta => "beta",
+            Channel::Stable => "stable",
+        }
+    }
+
+    pub fn is_nightly(&self) -> bool {
+        matches!(self.meta.channel, Channel::Nightly)
+    }
+
+    /// Target triple of the host.
+    pub fn host(&self) -> &str {
+        &self.meta.host
+    }
+
+    /// Path to the `rust-toolchain(.toml)` file that applies to the workspace, if there is any.
+    pub fn toolchain_file(&self) -> Option<&Path> {
+        self.toolchain_file.as_deref()
+    }
+}
+
+/// Creates a command that runs `rustc` (or `$RUSTC`) from the root directory of a workspace.
+pub fn rustc_command(workspace_root: &Path) -> Command {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| OsString::from("rustc"));
+    let mut cmd = Command::new(rustc);
+    cmd.current_dir(workspace_root);
+
+    // rustup sets `RUSTUP_TOOLCHAIN` for every proxied command, even if the toolchain was only
+    // resolved from the current directory. Unless the toolchain was overridden explicitly
+    // (e.g. `cargo +nightly`), let rustup resolve it again from the workspace root.
+    if matches!(
+        env::var("RUSTUP_TOOLCHAIN_SOURCE").as_deref(),
+        Ok("default" | "override" | "toolchain-file")
+    ) {
+        cmd.env_remove("RUSTUP_TOOLCHAIN");
+    }
+    cmd
+}
+
+/// Finds the `rust-toolchain` or `rust-toolchain.toml` file in the given directory or in any
+/// of its parents.
+fn find_toolchain_file(directory: &Path) -> Option<PathBuf> {
+    directory.ancestors().find_map(|dir| {
+        // rustup prefers the legacy file if both of them exist
+        ["rust-toolchain", "rust-toolchain.toml"]
+            .into_iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_toolchain_file;
+
+    #[test]
+    fn find_toolchain_file_in_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner = dir.path().join("crates").join("inner");
+        std::fs::create_dir_all(&inner).unwrap();
+        std::fs::write(dir.path().join("rust-toolchain.toml"), "").unwrap();
+
+        assert_eq!(
+            find_toolchain_file(&inner),
+            Some(dir.path().join("rust-toolchain.toml"))
+        );
+    }
+
+    #[test]
+    fn find_legacy_toolchain_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("rust-toolchain"), "").unwrap();
+        std::fs::write(dir.path().join("rust-toolchain.toml"), "").unwrap();
+
+        assert_eq!(
+            find_toolchain_file(dir.path()),
+            Some(dir.path().join("rust-toolchain"))
+        );
+    }
+}
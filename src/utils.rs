@@ -1,4 +1,5 @@
 use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 
 /// Find the number of cores on the current device, or return a default of `8`.
 pub fn get_core_count() -> i64 {
@@ -8,3 +9,22 @@ pub fn get_core_count() -> i64 {
         .try_into()
         .expect("Cannot convert number of CPUs")
 }
+
+/// Quick check if a program with the given name can be found.
+pub(crate) fn find_program_path(name: &str) -> Option<PathBuf> {
+    which::which(name).ok()
+}
+
+/// Quotes the given value using single quotes, so that it is interpreted literally by POSIX shells.
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r#"'\''"#))
+}
+
+/// Checks if both paths point to the same file or directory. Paths that cannot be canonicalized
+/// (e.g. because they do not exist) are compared as they are.
+pub(crate) fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
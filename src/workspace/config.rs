@@ -1,8 +1,9 @@
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-use crate::{Template, TemplateItemId, TomlValue};
-use anyhow::Context;
+use crate::{
+    ItemLocation, ItemRegistry, Template, TomlValue, WizardError, WizardOptions, WizardResult,
+};
 use toml_edit::{Array, DocumentMut, Formatted, Value, table, value};
 
 /// Config stored in `.cargo/config.toml` file.
@@ -14,17 +15,38 @@ pub struct CargoConfig {
 
 impl CargoConfig {
     pub fn empty_from_manifest(manifest_path: &Path) -> Self {
+        Self::empty(&config_path_from_manifest_path(manifest_path))
+    }
+
+    /// Creates an empty config that will be written to the given path.
+    pub fn empty(path: &Path) -> Self {
         Self {
-            path: config_path_from_manifest_path(manifest_path),
+            path: path.to_path_buf(),
             document: Default::default(),
         }
     }
 
-    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
-        let config = std::fs::read_to_string(path).context("Cannot read config.toml file")?;
+    /// Loads the config from the given path, or creates an empty config if the file does not
+    /// exist.
+    pub fn from_path_or_empty(path: &Path) -> WizardResult<Self> {
+        if path.exists() {
+            Self::from_path(path)
+        } else {
+            Ok(Self::empty(path))
+        }
+    }
+
+    pub fn from_path(path: &Path) -> WizardResult<Self> {
+        let config = std::fs::read_to_string(path).map_err(|source| WizardError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
         let document = config
             .parse::<DocumentMut>()
-            .context("Cannot parse config.toml file")?;
+            .map_err(|source| WizardError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })?;
 
         Ok(Self {
             document,
@@ -32,47 +54,166 @@ impl CargoConfig {
         })
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn get_text(&self) -> String {
         self.document.to_string()
     }
 
-    pub fn apply_template(mut self, template: &Template) -> anyhow::Result<Self> {
-        let rustflags: Vec<String> = template
-            .iter_items()
-            .filter_map(|(id, value)| {
-                let value = match value {
-                    TomlValue::String(value) => value.clone(),
-                    TomlValue::Int(value) => value.to_string(),
-                    TomlValue::Bool(value) => value.to_string(),
-                };
-                match id {
-                    TemplateItemId::TargetCpuInstructionSet => {
-                        Some(format!("-Ctarget-cpu={value}"))
-                    }
-                    TemplateItemId::FrontendThreads => Some(format!("-Zthreads={value}")),
-                    TemplateItemId::Linker => Some(format!("-Clink-arg=-fuse-ld={value}")),
-                    TemplateItemId::DebugInfo
-                    | TemplateItemId::Strip
-                    | TemplateItemId::Lto
-                    | TemplateItemId::CodegenUnits
-                    | TemplateItemId::Panic
-                    | TemplateItemId::OptimizationLevel
-                    | TemplateItemId::CodegenBackend
-                    | TemplateItemId::Incremental
-                    | TemplateItemId::SplitDebugInfo => None,
+    pub fn apply_template(
+        mut self,
+        template: &Template,
+        options: &WizardOptions,
+    ) -> WizardResult<Self> {
+        let registry = options.item_registry();
+        self.remove_items(template, registry);
+
+        if options.normalize_rustflags_enabled() {
+            self.normalize_rustflags();
+        }
+        let rustflags = template_rustflags(template, registry);
+        if !rustflags.is_empty() {
+            self.apply_rustflags(rustflags)?;
+        }
+
+        // Other config keys are simply overwritten
+        for (id, item_value) in template.iter_items() {
+            let (table_name, key, new_value) = match registry.location(id) {
+                Some(ItemLocation::Config { table, key }) => {
+                    (table, key, item_value.to_toml_value())
+                }
+                Some(ItemLocation::ConfigList { table, key }) => {
+                    let list = comma_list(item_value).ok_or_else(|| WizardError::InvalidType {
+                        path: self.path.clone(),
+                        key: format!("{table}.{key}"),
+                        expected: "a comma-separated string or an array",
+                    })?;
+                    (table, key, list)
                 }
+                _ => continue,
+            };
+            let table = self
+                .document
+                .entry(table_name)
+                .or_insert(table())
+                .as_table_mut()
+                .ok_or_else(|| WizardError::InvalidType {
+                    path: self.path.clone(),
+                    key: table_name.to_string(),
+                    expected: "a table",
+                })?;
+            let mut new_value = new_value;
+            if let Some(existing) = table.get(key).and_then(|item| item.as_value()) {
+                *new_value.decor_mut() = existing.decor().clone();
+            }
+            table.insert(key, value(new_value));
+        }
+
+        Ok(self)
+    }
+
+    /// Removes flags and keys of items that were removed from the template.
+    fn remove_items(&mut self, template: &Template, registry: &ItemRegistry) {
+        let removed_keys: Vec<_> = template
+            .iter_removed_items()
+            .filter_map(|id| match registry.location(id) {
+                Some(ItemLocation::Rustflag(key)) => Some(key),
+                _ => None,
             })
+            .map(|key| format!("{key}="))
             .collect();
-        if rustflags.is_empty() {
-            return Ok(self);
+        let is_removed = |flag: &str| removed_keys.iter().any(|key| flag.starts_with(key));
+
+        if let Some(build) = self
+            .document
+            .get_mut("build")
+            .and_then(|build| build.as_table_like_mut())
+        {
+            let is_empty = match build.get_mut("rustflags").and_then(|f| f.as_value_mut()) {
+                Some(Value::Array(array)) => {
+                    // Keep the formatting of the first item, in case it is removed
+                    let first_prefix = array.get(0).and_then(|item| item.decor().prefix()).cloned();
+                    array.retain(|flag| !flag.as_str().is_some_and(is_removed));
+                    if let Some(prefix) = first_prefix
+                        && let Some(first) = array.get_mut(0)
+                    {
+                        first.decor_mut().set_prefix(prefix);
+                    }
+                    array.is_empty()
+                }
+                Some(Value::String(val)) => {
+                    let flags: Vec<String> = val
+                        .value()
+                        .split_whitespace()
+                        .filter(|flag| !is_removed(flag))
+                        .map(|flag| flag.to_string())
+                        .collect();
+                    if flags.len() != val.value().split_whitespace().count() {
+                        let decor = val.decor().clone();
+                        *val = Formatted::new(flags.join(" "));
+                        *val.decor_mut() = decor;
+                    }
+                    flags.is_empty()
+                }
+                _ => false,
+            };
+            if is_empty && !removed_keys.is_empty() {
+                build.remove("rustflags");
+            }
+        }
+
+        let removed_config_keys =
+            template
+                .iter_removed_items()
+                .filter_map(|id| match registry.location(id) {
+                    Some(
+                        ItemLocation::Config { table, key }
+                        | ItemLocation::ConfigList { table, key },
+                    ) => Some((table, key)),
+                    _ => None,
+                });
+        for (table_name, key) in removed_config_keys {
+            if let Some(table) = self
+                .document
+                .get_mut(table_name)
+                .and_then(|table| table.as_table_like_mut())
+            {
+                table.remove(key);
+            }
         }
+    }
+
+    /// Converts `build.rustflags` from a string to an array of strings, if it is a string.
+    fn normalize_rustflags(&mut self) {
+        let Some(val) = self
+            .document
+            .get_mut("build")
+            .and_then(|build| build.as_table_like_mut())
+            .and_then(|build| build.get_mut("rustflags"))
+            .and_then(|flags| flags.as_value_mut())
+            .filter(|val| val.is_str())
+        else {
+            return;
+        };
+        let array = Array::from_iter(val.as_str().unwrap_or_default().split_whitespace());
+        let decor = val.decor().clone();
+        *val = Value::Array(array);
+        *val.decor_mut() = decor;
+    }
 
+    fn apply_rustflags(&mut self, rustflags: Vec<String>) -> WizardResult<()> {
         let build = self
             .document
             .entry("build")
             .or_insert(table())
             .as_table_mut()
-            .ok_or_else(|| anyhow::anyhow!("The build item in config.toml is not a table"))?;
+            .ok_or_else(|| WizardError::InvalidType {
+                path: self.path.clone(),
+                key: "build".to_string(),
+                expected: "a table",
+            })?;
         let flags = build.entry("rustflags").or_insert(value(Array::new()));
 
         let flag_map: HashMap<_, _> = rustflags
@@ -88,12 +229,9 @@ impl CargoConfig {
             // Find flags with the same key (e.g. -Ckey=val) and replace their values, to avoid
             // duplicating the keys.
             for item in array.iter_mut() {
-                if let Some(val) = item.as_str()
-                    && let Some((key, _)) = val.split_once('=')
-                    && let Some(new_value) = flag_map.get(key)
-                {
+                if let Some(new_flag) = item.as_str().and_then(|val| replace_flag(val, &flag_map)) {
                     let decor = item.decor().clone();
-                    let mut new_value = Value::String(Formatted::new(format!("{key}={new_value}")));
+                    let mut new_value = Value::String(Formatted::new(new_flag));
                     *new_value.decor_mut() = decor;
                     *item = new_value;
                 }
@@ -104,44 +242,234 @@ impl CargoConfig {
                 .filter_map(|v| v.as_str())
                 .map(|s| s.to_string())
                 .collect();
+            // Put new flags on separate lines if the existing flags are on separate lines
+            let line_prefix = array
+                .iter()
+                .last()
+                .and_then(|item| item.decor().prefix())
+                .and_then(|prefix| prefix.as_str())
+                .and_then(|prefix| prefix.rsplit_once('\n'))
+                .map(|(_, indent)| format!("\n{indent}"));
             for arg in rustflags {
-                if !existing_flags.contains(&arg) {
-                    array.push(Value::String(Formatted::new(arg)));
+                if !(is_standalone_flag(&arg) && existing_flags.contains(&arg)) {
+                    let mut new_value = Value::String(Formatted::new(arg));
+                    if let Some(prefix) = &line_prefix {
+                        new_value.decor_mut().set_prefix(prefix);
+                    }
+                    array.push_formatted(new_value);
                 }
             }
         } else if let Some(val) = flags.as_value_mut().filter(|v| v.is_str()) {
-            let flattened_flags = rustflags.join(" ");
-            let mut original_value = val.as_str().unwrap_or_default().to_string();
-            if !original_value.ends_with(' ') && !original_value.is_empty() {
-                original_value.push(' ');
+            // Strings are split on whitespace, which is how Cargo interprets them
+            let mut flags: Vec<String> = val
+                .as_str()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|flag| replace_flag(flag, &flag_map).unwrap_or_else(|| flag.to_string()))
+                .collect();
+            for arg in rustflags {
+                if !(is_standalone_flag(&arg) && flags.contains(&arg)) {
+                    flags.push(arg);
+                }
             }
-            original_value.push_str(&flattened_flags);
             let decor = val.decor().clone();
-            *val = Value::String(Formatted::new(original_value));
+            *val = Value::String(Formatted::new(flags.join(" ")));
             *val.decor_mut() = decor;
         } else {
-            return Err(anyhow::anyhow!(
-                "build.rustflags in config.toml is not a string or an array"
-            ));
+            return Err(WizardError::InvalidType {
+                path: self.path.clone(),
+                key: "build.rustflags".to_string(),
+                expected: "a string or an array",
+            });
         }
 
-        Ok(self)
+        Ok(())
     }
 
-    pub fn write(self) -> anyhow::Result<()> {
-        std::fs::create_dir_all(self.path.parent().expect("Missing config.toml parent"))
-            .context("Cannot create config.toml parent directory")?;
-        std::fs::write(&self.path, self.document.to_string())
-            .context("Cannot write config.toml manifest")?;
-        Ok(())
+    pub fn write(self) -> WizardResult<()> {
+        let parent = self.path.parent().expect("Missing config.toml parent");
+        std::fs::create_dir_all(parent).map_err(|source| WizardError::Write {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+        std::fs::write(&self.path, self.document.to_string()).map_err(|source| WizardError::Write {
+            path: self.path,
+            source,
+        })
+    }
+}
+
+/// Returns the rustflags that should be added to `build.rustflags` for the given template.
+pub(crate) fn template_rustflags(template: &Template, registry: &ItemRegistry) -> Vec<String> {
+    template
+        .iter_items()
+        .flat_map(|(id, value)| match registry.location(id) {
+            Some(ItemLocation::ExtraRustflags) => extra_rustflags(value),
+            Some(ItemLocation::Rustflag(key)) => vec![format!("{key}={}", rustflag_value(value))],
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Splits the value of the extra rustflags item into individual flags.
+pub(crate) fn extra_rustflags(value: &TomlValue) -> Vec<String> {
+    match value {
+        // Arrays are not split, so that their entries can contain whitespace
+        TomlValue::Array(values) => values.iter().map(rustflag_value).collect(),
+        value => rustflag_value(value)
+            .split_whitespace()
+            .map(|flag| flag.to_string())
+            .collect(),
     }
 }
 
+/// Formats the value of a rustflag (e.g. `native` in `-Ctarget-cpu=native`).
+pub(crate) fn rustflag_value(value: &TomlValue) -> String {
+    match value {
+        TomlValue::String(value) => value.clone(),
+        TomlValue::Int(value) => value.to_string(),
+        TomlValue::Bool(true) => "yes".to_string(),
+        TomlValue::Bool(false) => "no".to_string(),
+        TomlValue::Array(values) => values
+            .iter()
+            .map(rustflag_value)
+            .collect::<Vec<_>>()
+            .join(","),
+        TomlValue::Table(_) => value.to_toml_value().to_string(),
+    }
+}
+
+/// Checks if the flag is self-contained (e.g. `-Zshare-generics` or `-Copt-level=1`), so that it
+/// does not have to be added again if it is already present. Options whose value is passed in a
+/// separate flag (e.g. `-C opt-level=1`) and such values cannot be deduplicated, because that
+/// would separate the values from their options.
+fn is_standalone_flag(flag: &str) -> bool {
+    match flag.strip_prefix("--") {
+        Some(option) => option.contains('='),
+        None => flag.starts_with('-') && flag.len() > 2,
+    }
+}
+
+/// If the given flag has a key (e.g. `-Ckey=val`) whose value is set by the template, returns the
+/// flag with the new value.
+fn replace_flag(flag: &str, flag_map: &HashMap<String, String>) -> Option<String> {
+    let (key, _) = flag.split_once('=')?;
+    let new_value = flag_map.get(key)?;
+    Some(format!("{key}={new_value}"))
+}
+
+/// Converts a comma-separated list (e.g. `std,panic_abort`) into a TOML array.
+/// Arrays are used as they are, other values cannot be converted into a list.
+pub(crate) fn comma_list(value: &TomlValue) -> Option<Value> {
+    match value {
+        TomlValue::String(value) => Some(Value::Array(Array::from_iter(
+            value
+                .split(',')
+                .map(|item| item.trim())
+                .filter(|item| !item.is_empty()),
+        ))),
+        TomlValue::Array(_) => Some(value.to_toml_value()),
+        TomlValue::Int(_) | TomlValue::Bool(_) | TomlValue::Table(_) => None,
+    }
+}
+
+/// Finds the path of the config file in the root of the workspace.
+/// If no config file exists, `.cargo/config.toml` will be used.
 pub fn config_path_from_manifest_path(manifest_path: &Path) -> PathBuf {
-    manifest_path
+    let cargo_dir = manifest_path
         .parent()
-        .map(|p| p.join(".cargo").join("config.toml"))
-        .expect("Manifest path has no parent")
+        .map(|p| p.join(".cargo"))
+        .expect("Manifest path has no parent");
+    find_config_file(&cargo_dir).unwrap_or_else(|| cargo_dir.join("config.toml"))
+}
+
+/// Finds an existing config file in the given `.cargo` directory.
+/// Cargo prefers the legacy `config` file if both `config` and `config.toml` exist.
+pub(crate) fn find_config_file(cargo_dir: &Path) -> Option<PathBuf> {
+    ["config", "config.toml"]
+        .into_iter()
+        .map(|name| cargo_dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Returns the Cargo home directory (`$CARGO_HOME` or `~/.cargo`).
+pub(crate) fn cargo_home_dir() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Kind of a location where a Cargo config file can be stored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigLocationKind {
+    /// `.cargo` directory in the root of the workspace.
+    Workspace,
+    /// `.cargo` directory in a parent directory of the workspace.
+    ParentDirectory,
+    /// `$CARGO_HOME` directory.
+    CargoHome,
+}
+
+/// Location of a Cargo config file that can be modified by a template.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigLocation {
+    path: PathBuf,
+    kind: ConfigLocationKind,
+    exists: bool,
+}
+
+impl ConfigLocation {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn kind(&self) -> ConfigLocationKind {
+        self.kind
+    }
+
+    pub fn exists(&self) -> bool {
+        self.exists
+    }
+}
+
+/// Finds Cargo config files that apply to the workspace with the given manifest.
+/// The workspace and `$CARGO_HOME` locations are always included, even if the files do not
+/// exist yet, while configs from parent directories are included only if they exist.
+pub fn config_locations(manifest_path: &Path) -> Vec<ConfigLocation> {
+    let workspace_path = config_path_from_manifest_path(manifest_path);
+    let mut locations = vec![ConfigLocation {
+        exists: workspace_path.is_file(),
+        path: workspace_path,
+        kind: ConfigLocationKind::Workspace,
+    }];
+
+    let workspace_root = manifest_path.parent().expect("Manifest path has no parent");
+    let cargo_home = cargo_home_dir();
+    for dir in workspace_root.ancestors().skip(1) {
+        let cargo_dir = dir.join(".cargo");
+        if cargo_home.as_ref().is_some_and(|home| home == &cargo_dir) {
+            continue;
+        }
+        if let Some(path) = find_config_file(&cargo_dir) {
+            locations.push(ConfigLocation {
+                path,
+                kind: ConfigLocationKind::ParentDirectory,
+                exists: true,
+            });
+        }
+    }
+
+    if let Some(cargo_home) = cargo_home {
+        let path = find_config_file(&cargo_home).unwrap_or_else(|| cargo_home.join("config.toml"));
+        if !locations.iter().any(|location| location.path == path) {
+            locations.push(ConfigLocation {
+                exists: path.is_file(),
+                path,
+                kind: ConfigLocationKind::CargoHome,
+            });
+        }
+    }
+    locations
 }
 
 #[cfg(test)]
@@ -151,13 +479,18 @@ mod tests {
     use toml_edit::DocumentMut;
 
     use crate::template::TemplateBuilder;
+    use crate::workspace::config::{
+        ConfigLocationKind, config_locations, config_path_from_manifest_path,
+    };
     use crate::workspace::manifest::BuiltinProfile;
-    use crate::{CargoConfig, Template, TemplateItemId, TomlValue};
+    use crate::{CargoConfig, Template, TemplateItemId, TomlValue, WizardError, WizardOptions};
 
     #[test]
     fn create_rustflags() {
         let template = create_template(&[(TemplateItemId::TargetCpuInstructionSet, "native")]);
-        let config = create_empty_config().apply_template(&template).unwrap();
+        let config = create_empty_config()
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
         insta::assert_snapshot!(config.get_text(), @r###"
         [build]
         rustflags = ["-Ctarget-cpu=native"]
@@ -173,7 +506,9 @@ mod tests {
 rustflags = ["-Cbar=foo"]
 "#,
         );
-        let config = config.apply_template(&template).unwrap();
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
         insta::assert_snapshot!(config.get_text(), @r###"
     [build]
     rustflags = ["-Cbar=foo", "-Ctarget-cpu=native"]
@@ -189,7 +524,9 @@ rustflags = ["-Cbar=foo"]
 rustflags = ["-Ctarget-cpu=foo"]
 "#,
         );
-        let config = config.apply_template(&template).unwrap();
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
         insta::assert_snapshot!(config.get_text(), @r###"
         [build]
         rustflags = ["-Ctarget-cpu=foo"]
@@ -205,7 +542,9 @@ rustflags = ["-Ctarget-cpu=foo"]
 rustflags = ""
 "#,
         );
-        let config = config.apply_template(&template).unwrap();
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
         insta::assert_snapshot!(config.get_text(), @r###"
             [build]
             rustflags = "-Ctarget-cpu=native"
@@ -221,7 +560,9 @@ rustflags = ""
 rustflags = "-Cfoo=bar"
 "#,
         );
-        let config = config.apply_template(&template).unwrap();
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
         insta::assert_snapshot!(config.get_text(), @r###"
         [build]
         rustflags = "-Cfoo=bar -Ctarget-cpu=native"
@@ -237,13 +578,78 @@ rustflags = "-Cfoo=bar"
 rustflags = "-Cfoo=bar" # Foo
 "#,
         );
-        let config = config.apply_template(&template).unwrap();
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
         insta::assert_snapshot!(config.get_text(), @r###"
         [build]
         rustflags = "-Cfoo=bar -Ctarget-cpu=native" # Foo
         "###);
     }
 
+    #[test]
+    fn replace_string_rustflag_value() {
+        let template = create_template(&[(TemplateItemId::TargetCpuInstructionSet, "native")]);
+        let config = create_config(
+            r#"
+[build]
+rustflags = "-Ctarget-cpu=foo  -Cbar=baz" # Foo
+"#,
+        );
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+        [build]
+        rustflags = "-Ctarget-cpu=native -Cbar=baz" # Foo
+        "###);
+    }
+
+    #[test]
+    fn normalize_string_rustflags() {
+        let template = create_template(&[(TemplateItemId::TargetCpuInstructionSet, "native")]);
+        let config = create_config(
+            r#"
+[build]
+# Bar
+rustflags = "-Ctarget-cpu=foo -Cbar=baz" # Foo
+"#,
+        );
+        let config = config
+            .apply_template(
+                &template,
+                &WizardOptions::default().with_normalized_rustflags(),
+            )
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+        [build]
+        # Bar
+        rustflags = ["-Ctarget-cpu=native", "-Cbar=baz"] # Foo
+        "###);
+    }
+
+    #[test]
+    fn normalize_rustflags_without_template_rustflags() {
+        let template = create_template(&[(TemplateItemId::BuildTarget, "wasm32-unknown-unknown")]);
+        let config = create_config(
+            r#"
+[build]
+rustflags = "-Ctarget-cpu=foo -Cbar=baz" # Foo
+"#,
+        );
+        let config = config
+            .apply_template(
+                &template,
+                &WizardOptions::default().with_normalized_rustflags(),
+            )
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+        [build]
+        rustflags = ["-Ctarget-cpu=foo", "-Cbar=baz"] # Foo
+        target = "wasm32-unknown-unknown"
+        "###);
+    }
+
     #[test]
     fn replace_rustflag_value() {
         let template = create_template(&[(TemplateItemId::TargetCpuInstructionSet, "native")]);
@@ -257,7 +663,9 @@ rustflags = [
 ]
 "#,
         );
-        let config = config.apply_template(&template).unwrap();
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
         insta::assert_snapshot!(config.get_text(), @r###"
 
         [build]
@@ -269,10 +677,246 @@ rustflags = [
         "###);
     }
 
+    #[test]
+    fn add_extra_rustflags() {
+        let template = create_template(&[
+            (TemplateItemId::TargetCpuInstructionSet, "native"),
+            (
+                TemplateItemId::ExtraRustflags,
+                "-Cforce-frame-pointers=yes -Zshare-generics",
+            ),
+        ]);
+        let config = create_config(
+            r#"
+[build]
+rustflags = ["-Cforce-frame-pointers=no", "-Zshare-generics"]
+"#,
+        );
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+
+        [build]
+        rustflags = ["-Cforce-frame-pointers=yes", "-Zshare-generics", "-Ctarget-cpu=native"]
+        "###);
+    }
+
+    #[test]
+    fn bool_rustflags() {
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(TemplateItemId::ForceFramePointers, TomlValue::Bool(true))
+            .item(TemplateItemId::EmbedBitcode, TomlValue::Bool(false))
+            .build();
+        let config = create_empty_config()
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+        [build]
+        rustflags = ["-Cforce-frame-pointers=yes", "-Cembed-bitcode=no"]
+        "###);
+    }
+
+    #[test]
+    fn array_values() {
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::ExtraRustflags,
+                TomlValue::array(vec![
+                    TomlValue::string("-Clink-arg=-Wl,--foo bar"),
+                    TomlValue::string("-Zshare-generics"),
+                ]),
+            )
+            .item(
+                TemplateItemId::BuildStd,
+                TomlValue::array(vec![
+                    TomlValue::string("std"),
+                    TomlValue::string("panic_abort"),
+                ]),
+            )
+            .build();
+        let config = create_empty_config()
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+        [build]
+        rustflags = ["-Clink-arg=-Wl,--foo bar", "-Zshare-generics"]
+
+        [unstable]
+        build-std = ["std", "panic_abort"]
+        "###);
+    }
+
+    #[test]
+    fn build_std() {
+        let template = create_template(&[
+            (TemplateItemId::BuildStd, "std,panic_abort"),
+            (TemplateItemId::BuildStdFeatures, "optimize_for_size"),
+            (TemplateItemId::BuildTarget, "x86_64-unknown-linux-gnu"),
+        ]);
+        let config = create_config(
+            r#"
+[build]
+target = "aarch64-unknown-linux-gnu" # Foo
+"#,
+        );
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+
+        [build]
+        target = "x86_64-unknown-linux-gnu" # Foo
+
+        [unstable]
+        build-std = ["std", "panic_abort"]
+        build-std-features = ["optimize_for_size"]
+        "###);
+    }
+
+    #[test]
+    fn keep_split_flags() {
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::ExtraRustflags,
+                TomlValue::string("-C debuginfo=2 -Zshare-generics"),
+            )
+            .build();
+        let config = create_config(
+            r#"
+[build]
+rustflags = ["-C", "opt-level=1", "-Zshare-generics"]
+"#,
+        );
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+        [build]
+        rustflags = ["-C", "opt-level=1", "-Zshare-generics", "-C", "debuginfo=2"]
+        "###);
+    }
+
+    #[test]
+    fn remove_unset_rustflags() {
+        let mut template = create_template(&[(TemplateItemId::EmbedBitcode, "no")]);
+        template.remove_item(TemplateItemId::TargetCpuInstructionSet);
+        template.remove_item(TemplateItemId::Linker);
+        let config = create_config(
+            r#"
+[build]
+rustflags = [
+    "-Ctarget-cpu=native", # Foo
+    "-Clink-arg=-fuse-ld=lld",
+    "-Cdebuginfo=1",
+]
+"#,
+        );
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+        [build]
+        rustflags = [
+            "-Cdebuginfo=1",
+            "-Cembed-bitcode=no",
+        ]
+        "###);
+    }
+
+    #[test]
+    fn remove_unset_string_rustflags() {
+        let mut template = create_template(&[]);
+        template.remove_item(TemplateItemId::TargetCpuInstructionSet);
+        template.remove_item(TemplateItemId::BuildTarget);
+        let config = create_config(
+            r#"
+[build]
+rustflags = "-Ctarget-cpu=native" # Foo
+target = "x86_64-unknown-linux-gnu"
+"#,
+        );
+        let config = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap();
+        insta::assert_snapshot!(config.get_text(), @r###"
+
+        [build]
+        "###);
+    }
+
+    #[test]
+    fn invalid_rustflags_type() {
+        let template = create_template(&[(TemplateItemId::TargetCpuInstructionSet, "native")]);
+        let config = create_config(
+            r#"
+[build]
+rustflags = 1
+"#,
+        );
+        let error = config
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap_err();
+        assert!(matches!(
+            &error,
+            WizardError::InvalidType { key, .. } if key == "build.rustflags"
+        ));
+    }
+
+    #[test]
+    fn invalid_list_value_type() {
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(TemplateItemId::BuildStd, TomlValue::Int(1))
+            .build();
+        let error = create_empty_config()
+            .apply_template(&template, &WizardOptions::default())
+            .unwrap_err();
+        assert!(matches!(
+            &error,
+            WizardError::InvalidType { key, .. } if key == "unstable.build-std"
+        ));
+    }
+
+    #[test]
+    fn detect_legacy_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        assert_eq!(
+            config_path_from_manifest_path(&manifest_path),
+            dir.path().join(".cargo").join("config.toml")
+        );
+
+        std::fs::create_dir_all(dir.path().join(".cargo")).unwrap();
+        std::fs::write(dir.path().join(".cargo").join("config"), "").unwrap();
+        assert_eq!(
+            config_path_from_manifest_path(&manifest_path),
+            dir.path().join(".cargo").join("config")
+        );
+    }
+
+    #[test]
+    fn find_config_locations_in_parent_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("workspace");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(dir.path().join(".cargo")).unwrap();
+        std::fs::write(dir.path().join(".cargo").join("config.toml"), "").unwrap();
+
+        let locations = config_locations(&root.join("Cargo.toml"));
+        assert_eq!(locations[0].kind(), ConfigLocationKind::Workspace);
+        assert!(!locations[0].exists());
+        assert_eq!(locations[1].kind(), ConfigLocationKind::ParentDirectory);
+        assert_eq!(
+            locations[1].path(),
+            dir.path().join(".cargo").join("config.toml")
+        );
+        assert!(locations[1].exists());
+    }
+
     fn create_template(items: &[(TemplateItemId, &str)]) -> Template {
         let mut builder = TemplateBuilder::new(BuiltinProfile::Release);
         for (id, value) in items {
-            builder = builder.item(*id, TomlValue::String(value.to_string()));
+            builder = builder.item(id.clone(), TomlValue::String(value.to_string()));
         }
         builder.build()
     }
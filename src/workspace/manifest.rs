@@ -1,17 +1,19 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use anyhow::Context;
+use rustc_version::Version;
 use toml_edit::{Array, DocumentMut, Item, Value, table, value};
 
-use crate::template::{TemplateItemId, dev_profile, release_profile};
-use crate::{Template, TomlValue};
+use crate::template::TemplateItemId;
+use crate::utils::same_path;
+use crate::{ItemRegistry, Template, TomlValue, WizardError, WizardOptions, WizardResult};
 
 /// Tries to resolve the workspace root manifest (Cargo.toml) path from the current directory.
-pub fn resolve_manifest_path() -> anyhow::Result<PathBuf> {
+pub fn resolve_manifest_path() -> WizardResult<PathBuf> {
     let cmd = cargo_metadata::MetadataCommand::new();
     let metadata = cmd
         .exec()
-        .map_err(|error| anyhow::anyhow!("Cannot get cargo metadata: {:?}", error))?;
+        .map_err(|source| WizardError::Metadata { path: None, source })?;
     let manifest_path = metadata
         .workspace_root
         .into_std_path_buf()
@@ -19,7 +21,71 @@ pub fn resolve_manifest_path() -> anyhow::Result<PathBuf> {
     Ok(manifest_path)
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Manifests of a Cargo workspace, resolved using `cargo metadata`.
+#[derive(Debug)]
+pub struct WorkspaceManifests {
+    root: PathBuf,
+    members: Vec<PathBuf>,
+}
+
+impl WorkspaceManifests {
+    /// Resolves the workspace that contains the manifest with the given path.
+    pub fn resolve(manifest_path: &Path) -> WizardResult<Self> {
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(manifest_path)
+            .no_deps()
+            .exec()
+            .map_err(|source| WizardError::Metadata {
+                path: Some(manifest_path.to_path_buf()),
+                source,
+            })?;
+        let root = metadata
+            .workspace_root
+            .clone()
+            .into_std_path_buf()
+            .join("Cargo.toml");
+        let members = metadata
+            .workspace_packages()
+            .into_iter()
+            .map(|package| package.manifest_path.clone().into_std_path_buf())
+            .collect();
+        Ok(Self { root, members })
+    }
+
+    /// Path to the root manifest of the workspace.
+    pub fn root_manifest(&self) -> &Path {
+        &self.root
+    }
+
+    /// Checks if the given path points to the root manifest of the workspace.
+    pub fn is_root_manifest(&self, manifest_path: &Path) -> bool {
+        same_path(manifest_path, &self.root)
+    }
+
+    /// Finds manifests of workspace members (other than the root manifest) that contain
+    /// a `[profile]` section, which is ignored by Cargo.
+    pub fn members_with_ignored_profiles(&self) -> WizardResult<Vec<PathBuf>> {
+        let mut manifests = vec![];
+        for path in self
+            .members
+            .iter()
+            .filter(|path| !same_path(path, &self.root))
+        {
+            let manifest = CargoManifest::from_path(path)?;
+            if !manifest.get_profiles().is_empty() {
+                manifests.push(path.clone());
+            }
+        }
+        Ok(manifests)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum BuiltinProfile {
     Dev,
     Release,
@@ -34,13 +100,22 @@ impl BuiltinProfile {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Profile {
     Builtin(BuiltinProfile),
     Custom(String),
 }
 
 impl Profile {
+    /// Creates a profile from its name, without performing any validation.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "dev" => Self::dev(),
+            "release" => Self::release(),
+            custom => Self::Custom(custom.to_string()),
+        }
+    }
+
     pub fn dev() -> Self {
         Self::Builtin(BuiltinProfile::Dev)
     }
@@ -69,18 +144,32 @@ pub struct CargoManifest {
 }
 
 impl CargoManifest {
-    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
-        let manifest = std::fs::read_to_string(path)
-            .with_context(|| format!("Cannot read Cargo.toml manifest from {}", path.display()))?;
+    pub fn from_path(path: &Path) -> WizardResult<Self> {
+        let manifest = std::fs::read_to_string(path).map_err(|source| match source.kind() {
+            std::io::ErrorKind::NotFound => WizardError::ManifestNotFound {
+                path: path.to_path_buf(),
+            },
+            _ => WizardError::Read {
+                path: path.to_path_buf(),
+                source,
+            },
+        })?;
         let document = manifest
             .parse::<DocumentMut>()
-            .with_context(|| format!("Cannot parse Cargo.toml manifest from {}", path.display()))?;
+            .map_err(|source| WizardError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })?;
         Ok(Self {
             document,
             path: path.to_path_buf(),
         })
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn get_profiles(&self) -> Vec<String> {
         self.document
             .get("profile")
@@ -89,65 +178,168 @@ impl CargoManifest {
             .unwrap_or_default()
     }
 
+    /// Returns the minimum supported Rust version (`rust-version`) of the package or of the
+    /// workspace.
+    /// If the package inherits the version (`rust-version.workspace = true`), the version of the
+    /// workspace is used.
+    pub fn rust_version(&self) -> Option<Version> {
+        let package_version = self
+            .document
+            .get("package")
+            .and_then(|p| p.get("rust-version"))
+            .and_then(|v| v.as_str());
+        let workspace_version = self
+            .document
+            .get("workspace")
+            .and_then(|w| w.get("package"))
+            .and_then(|p| p.get("rust-version"))
+            .and_then(|v| v.as_str());
+        package_version
+            .or(workspace_version)
+            .and_then(parse_partial_version)
+    }
+
     pub fn get_text(&self) -> String {
         self.document.to_string()
     }
 
+    /// Returns the chain of profiles that the given profile inherits from, starting with the
+    /// profile itself and ending with a built-in profile.
+    pub fn inheritance_chain(&self, profile: &Profile) -> WizardResult<Vec<Profile>> {
+        let mut chain = vec![profile.clone()];
+        while let Some(Profile::Custom(name)) = chain.last() {
+            let inherits = self
+                .document
+                .get("profile")
+                .and_then(|p| p.get(name))
+                .and_then(|p| p.get("inherits"))
+                .and_then(|i| i.as_str());
+            let parent = match (name.as_str(), inherits) {
+                (_, Some(inherits)) => Profile::from_name(inherits),
+                // Built-in profiles used by `cargo test` and `cargo bench`
+                ("test", None) => Profile::dev(),
+                ("bench", None) => Profile::release(),
+                (name, None) => {
+                    return Err(WizardError::MissingInherits {
+                        path: self.path.clone(),
+                        profile: name.to_string(),
+                    });
+                }
+            };
+            if chain.contains(&parent) {
+                chain.push(parent);
+                return Err(WizardError::InheritanceCycle {
+                    chain: profile_names(&chain),
+                });
+            }
+            chain.push(parent);
+        }
+        Ok(chain)
+    }
+
+    /// Checks that the profile can inherit from the given parent profile without creating an
+    /// inheritance cycle.
+    pub fn check_inheritance(&self, profile: &Profile, parent: &Profile) -> WizardResult<()> {
+        let parents = self.inheritance_chain(parent)?;
+        if let Some(index) = parents.iter().position(|p| p == profile) {
+            let mut cycle = vec![profile.clone()];
+            cycle.extend_from_slice(&parents[..=index]);
+            return Err(WizardError::InheritanceCycle {
+                chain: profile_names(&cycle),
+            });
+        }
+        Ok(())
+    }
+
+    /// Resolves the values of template items that the given profile would use, including values
+    /// inherited from its parent profiles and the defaults of the built-in profile at the end of
+    /// the inheritance chain.
+    pub fn resolve_profile_values(
+        &self,
+        profile: &Profile,
+        registry: &ItemRegistry,
+    ) -> WizardResult<HashMap<TemplateItemId, TomlValue>> {
+        let chain = self.inheritance_chain(profile)?;
+        let base = match chain.last() {
+            Some(Profile::Builtin(base)) => *base,
+            _ => unreachable!("Inheritance chain does not end with a built-in profile"),
+        };
+        let mut values: HashMap<_, _> = registry.default_values(base).into_iter().collect();
+
+        // Values from profiles closer to the original profile take precedence
+        for profile in chain.iter().rev() {
+            let Some(table) = self
+                .document
+                .get("profile")
+                .and_then(|p| p.get(profile.name()))
+                .and_then(|p| p.as_table_like())
+            else {
+                continue;
+            };
+            for (name, item) in table.iter() {
+                if let Some(id) = registry.find_profile_item(name)
+                    && let Some(value) = TomlValue::from_toml_item(item)
+                {
+                    values.insert(id, value);
+                }
+            }
+        }
+        Ok(values)
+    }
+
     pub fn apply_template(
         mut self,
         profile: &Profile,
         template: &Template,
-    ) -> anyhow::Result<Self> {
+        options: &WizardOptions,
+    ) -> WizardResult<Self> {
+        if !profile.is_builtin() {
+            self.check_inheritance(profile, template.inherits())?;
+        }
+
+        let registry = options.item_registry();
+
+        // We skip the items that would have the same value even without applying the template.
+        // For built-in profiles (dev or release), these are their default values, for custom
+        // profiles the values inherited from their parent profile.
+        let base_values = match profile {
+            Profile::Builtin(_) => self.resolve_profile_values(profile, registry)?,
+            Profile::Custom(_) if options.write_inherited_values_enabled() => HashMap::new(),
+            Profile::Custom(_) => self.resolve_profile_values(template.inherits(), registry)?,
+        };
+
         let profiles_table = self
             .document
             .entry("profile")
             .or_insert(table())
             .as_table_mut()
-            .ok_or_else(|| anyhow::anyhow!("The profile item in Cargo.toml is not a table"))?;
+            .ok_or_else(|| WizardError::InvalidType {
+                path: self.path.clone(),
+                key: "profile".to_string(),
+                expected: "a table",
+            })?;
         profiles_table.set_dotted(true);
 
         let profile_table = profiles_table
             .entry(profile.name())
             .or_insert(table())
             .as_table_mut()
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "The profile.{} table in Cargo.toml is not a table",
-                    profile.name()
-                )
+            .ok_or_else(|| WizardError::InvalidType {
+                path: self.path.clone(),
+                key: format!("profile.{}", profile.name()),
+                expected: "a table",
             })?;
 
-        // If we're applying the template to a built-in profile (dev or release), we skip the items
-        // that still have the default value.
-        // However, we don't do that for custom profiles based on dev/release, since dev/release
-        // might not actually contain the default values in that case.
-        let base_template = if profile.is_builtin() {
-            Some(match template.inherits() {
-                BuiltinProfile::Dev => dev_profile().build(),
-                BuiltinProfile::Release => release_profile().build(),
-            })
-        } else {
-            None
-        };
         let mut values: Vec<_> = template
             .iter_items()
             .filter_map(|(id, value)| {
-                let name = id_to_item_name(id)?;
+                let name = registry.profile_key(id)?;
 
                 // Check if there is any existing value in the TOML profile table
-                let existing_value = profile_table.get(name).and_then(|item| {
-                    if let Some(value) = item.as_bool() {
-                        Some(TomlValue::Bool(value))
-                    } else if let Some(value) = item.as_integer() {
-                        Some(TomlValue::Int(value))
-                    } else {
-                        item.as_str()
-                            .map(|value| TomlValue::String(value.to_string()))
-                    }
-                });
-                // Check if we modify a built-in profile, and if we have a default vaule for this
-                // item in the profile.
-                let default_item = base_template.as_ref().and_then(|t| t.get_item(id).cloned());
+                let existing_value = profile_table.get(name).and_then(TomlValue::from_toml_item);
+                // Check if the profile would inherit the same value from its parent profile
+                // (or from the defaults of a built-in profile).
+                let default_item = base_values.get(id).cloned();
 
                 // If we have the same value as the default, and the existing value also matches the
                 // default, skip this item.
@@ -182,9 +374,15 @@ impl CargoManifest {
                 profile_table.insert(&entry.name, value(new_value));
             }
         }
+        for name in template
+            .iter_removed_items()
+            .filter_map(|id| registry.profile_key(id))
+        {
+            profile_table.remove(name);
+        }
 
         // Add necessary Cargo features
-        if template.get_item(TemplateItemId::CodegenBackend).is_some()
+        if template.get_item(&TemplateItemId::CodegenBackend).is_some()
             && let Some(features) = self
                 .document
                 .entry("cargo-features")
@@ -200,28 +398,28 @@ impl CargoManifest {
         Ok(self)
     }
 
-    pub fn write(self) -> anyhow::Result<()> {
-        std::fs::write(self.path, self.document.to_string())
-            .context("Cannot write Cargo.toml manifest")?;
-        Ok(())
+    pub fn write(self) -> WizardResult<()> {
+        std::fs::write(&self.path, self.document.to_string()).map_err(|source| WizardError::Write {
+            path: self.path,
+            source,
+        })
     }
 }
 
-fn id_to_item_name(id: TemplateItemId) -> Option<&'static str> {
-    match id {
-        TemplateItemId::DebugInfo => Some("debug"),
-        TemplateItemId::SplitDebugInfo => Some("split-debuginfo"),
-        TemplateItemId::Strip => Some("strip"),
-        TemplateItemId::Lto => Some("lto"),
-        TemplateItemId::CodegenUnits => Some("codegen-units"),
-        TemplateItemId::Panic => Some("panic"),
-        TemplateItemId::OptimizationLevel => Some("opt-level"),
-        TemplateItemId::CodegenBackend => Some("codegen-backend"),
-        TemplateItemId::Incremental => Some("incremental"),
-        TemplateItemId::TargetCpuInstructionSet
-        | TemplateItemId::FrontendThreads
-        | TemplateItemId::Linker => None,
+fn profile_names(chain: &[Profile]) -> Vec<String> {
+    chain.iter().map(|p| p.name().to_string()).collect()
+}
+
+/// Parses a version that might be missing the minor or patch component (e.g. `1.70`).
+fn parse_partial_version(text: &str) -> Option<Version> {
+    let mut parts = text.trim().split('.').map(|part| part.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().transpose().ok()?.unwrap_or(0);
+    let patch = parts.next().transpose().ok()?.unwrap_or(0);
+    if parts.next().is_some() {
+        return None;
     }
+    Some(Version::new(major, minor, patch))
 }
 
 #[derive(Clone, Debug)]
@@ -238,3 +436,176 @@ impl TableItem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rustc_version::Version;
+    use toml_edit::DocumentMut;
+
+    use crate::workspace::manifest::{CargoManifest, Profile, parse_partial_version};
+    use crate::{ItemRegistry, TemplateItemId, TomlValue, WizardError};
+
+    #[test]
+    fn resolve_inheritance_chain() {
+        let manifest = create_manifest(
+            r#"
+[profile.dist]
+inherits = "release"
+
+[profile.dist-lto]
+inherits = "dist"
+"#,
+        );
+        assert_eq!(
+            manifest
+                .inheritance_chain(&Profile::Custom("dist-lto".to_string()))
+                .unwrap(),
+            vec![
+                Profile::Custom("dist-lto".to_string()),
+                Profile::Custom("dist".to_string()),
+                Profile::release()
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_inheritance_chain_missing_profile() {
+        let manifest = create_manifest("");
+        assert!(matches!(
+            manifest.inheritance_chain(&Profile::Custom("dist".to_string())),
+            Err(WizardError::MissingInherits { profile, .. }) if profile == "dist"
+        ));
+    }
+
+    #[test]
+    fn load_missing_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        assert!(matches!(
+            CargoManifest::from_path(&path),
+            Err(WizardError::ManifestNotFound { path: error_path }) if error_path == path
+        ));
+    }
+
+    #[test]
+    fn detect_existing_inheritance_cycle() {
+        let manifest = create_manifest(
+            r#"
+[profile.a]
+inherits = "b"
+
+[profile.b]
+inherits = "a"
+"#,
+        );
+        let error = manifest
+            .inheritance_chain(&Profile::Custom("a".to_string()))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Profile inheritance cycle detected: a -> b -> a"
+        );
+    }
+
+    #[test]
+    fn detect_new_inheritance_cycle() {
+        let manifest = create_manifest(
+            r#"
+[profile.a]
+inherits = "release"
+
+[profile.b]
+inherits = "a"
+"#,
+        );
+        let error = manifest
+            .check_inheritance(
+                &Profile::Custom("a".to_string()),
+                &Profile::Custom("b".to_string()),
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Profile inheritance cycle detected: a -> b -> a"
+        );
+        assert!(
+            manifest
+                .check_inheritance(
+                    &Profile::Custom("c".to_string()),
+                    &Profile::Custom("b".to_string())
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn resolve_inherited_values() {
+        let manifest = create_manifest(
+            r#"
+[profile.release]
+lto = "thin"
+
+[profile.dist]
+inherits = "release"
+lto = true
+panic = "abort"
+
+[profile.dist-lto]
+inherits = "dist"
+codegen-units = 1
+"#,
+        );
+        let values = manifest
+            .resolve_profile_values(
+                &Profile::Custom("dist-lto".to_string()),
+                &ItemRegistry::builtin(),
+            )
+            .unwrap();
+        assert_eq!(values[&TemplateItemId::Lto], TomlValue::Bool(true));
+        assert_eq!(
+            values[&TemplateItemId::Panic],
+            TomlValue::String("abort".to_string())
+        );
+        assert_eq!(values[&TemplateItemId::CodegenUnits], TomlValue::Int(1));
+        assert_eq!(
+            values[&TemplateItemId::OptimizationLevel],
+            TomlValue::Int(3)
+        );
+    }
+
+    fn create_manifest(text: &str) -> CargoManifest {
+        CargoManifest {
+            path: Default::default(),
+            document: DocumentMut::from_str(text).unwrap(),
+        }
+    }
+
+    #[test]
+    fn parse_rust_version() {
+        assert_eq!(parse_partial_version("1"), Some(Version::new(1, 0, 0)));
+        assert_eq!(parse_partial_version("1.70"), Some(Version::new(1, 70, 0)));
+        assert_eq!(
+            parse_partial_version("1.70.1"),
+            Some(Version::new(1, 70, 1))
+        );
+        assert_eq!(parse_partial_version("1.70.1.0"), None);
+        assert_eq!(parse_partial_version("1.x"), None);
+    }
+
+    #[test]
+    fn inherit_rust_version_from_workspace() {
+        let manifest = create_manifest(
+            r#"
+[package]
+name = "foo"
+rust-version.workspace = true
+
+[workspace.package]
+rust-version = "1.70"
+"#,
+        );
+        assert_eq!(manifest.rust_version(), Some(Version::new(1, 70, 0)));
+    }
+}
@@ -1,21 +1,33 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-use anyhow::Context;
+use rustc_version::Version;
 
-use crate::Template;
+use crate::{
+    ItemLocation, ItemRegistry, ProfileAudit, Template, TemplateItemId, TomlValue, WizardError,
+    WizardOptions, WizardResult, audit_profiles,
+};
 use manifest::CargoManifest;
 
-use crate::workspace::config::{CargoConfig, config_path_from_manifest_path};
+use crate::workspace::config::{
+    CargoConfig, ConfigLocation, config_locations, config_path_from_manifest_path, extra_rustflags,
+    rustflag_value, template_rustflags,
+};
 use crate::workspace::manifest::Profile;
+use crate::workspace::rustflags::{RustflagsOverride, find_rustflags_overrides};
+use crate::workspace::toolchain_file::{ToolchainFile, toolchain_file_path_from_manifest_path};
 
 pub mod config;
 pub mod manifest;
+pub mod rustflags;
+pub mod toolchain_file;
 
 /// Cargo workspace project.
 #[derive(Clone)]
 pub struct CargoWorkspace {
     manifest: CargoManifest,
     config: CargoConfig,
+    toolchain_file: ToolchainFile,
 }
 
 impl CargoWorkspace {
@@ -23,9 +35,45 @@ impl CargoWorkspace {
         self,
         profile: &Profile,
         template: &Template,
-    ) -> anyhow::Result<ModifiedWorkspace> {
+        options: &WizardOptions,
+    ) -> WizardResult<ModifiedWorkspace> {
+        self.apply_templates(&[(profile, template)], options)
+    }
+
+    /// Applies several templates, each to its own profile, and returns a single combined
+    /// modification of the workspace.
+    ///
+    /// Rustflags and config keys (e.g. `build.target`) are shared by all profiles, so the
+    /// templates must not set different values for them.
+    pub fn apply_templates(
+        self,
+        templates: &[(&Profile, &Template)],
+        options: &WizardOptions,
+    ) -> WizardResult<ModifiedWorkspace> {
+        check_shared_settings(templates, options.item_registry())?;
+
+        // Flags in `build.rustflags` would have no effect if they are overridden
+        let mut rustflags_overrides: Vec<RustflagsOverride> = vec![];
+        for (_, template) in templates {
+            if template_rustflags(template, options.item_registry()).is_empty() {
+                continue;
+            }
+            let target = match template.get_item(&TemplateItemId::BuildTarget) {
+                Some(TomlValue::String(target)) => Some(target.as_str()),
+                _ => options.toolchain().map(|toolchain| toolchain.host()),
+            };
+            for rustflags_override in find_rustflags_overrides(self.root(), target) {
+                if !rustflags_overrides.contains(&rustflags_override) {
+                    rustflags_overrides.push(rustflags_override);
+                }
+            }
+        }
+
         let old_manifest = self.manifest.clone();
-        let new_manifest = self.manifest.apply_template(profile, template)?;
+        let mut new_manifest = self.manifest;
+        for (profile, template) in templates {
+            new_manifest = new_manifest.apply_template(profile, template, options)?;
+        }
         let manifest = if old_manifest.get_text() == new_manifest.get_text() {
             ModificationResult::NoChange
         } else {
@@ -35,8 +83,16 @@ impl CargoWorkspace {
             }
         };
 
-        let old_config = self.config.clone();
-        let new_config = self.config.apply_template(template)?;
+        let config = match options.config_path() {
+            Some(path) if path != self.config.path() => CargoConfig::from_path_or_empty(path)?,
+            _ => self.config,
+        };
+        let config_path = config.path().to_path_buf();
+        let old_config = config.clone();
+        let mut new_config = config;
+        for (_, template) in templates {
+            new_config = new_config.apply_template(template, options)?;
+        }
         let config = if old_config.get_text() == new_config.get_text() {
             ModificationResult::NoChange
         } else {
@@ -45,18 +101,89 @@ impl CargoWorkspace {
                 new: new_config,
             }
         };
-        Ok(ModifiedWorkspace { manifest, config })
+
+        let toolchain_file = if options.pin_nightly_toolchain_enabled() {
+            let old_file = self.toolchain_file.clone();
+            let mut new_file = self.toolchain_file;
+            for (_, template) in templates {
+                new_file = new_file.pin_nightly(template)?;
+            }
+            if old_file.get_text() == new_file.get_text() {
+                ModificationResult::NoChange
+            } else {
+                ModificationResult::Modified {
+                    old: old_file,
+                    new: new_file,
+                }
+            }
+        } else {
+            ModificationResult::NoChange
+        };
+
+        Ok(ModifiedWorkspace {
+            manifest,
+            config,
+            config_path,
+            toolchain_file,
+            rustflags_overrides,
+        })
+    }
+
+    /// Root directory of the workspace.
+    pub fn root(&self) -> &Path {
+        self.manifest
+            .path()
+            .parent()
+            .expect("Manifest has no parent")
+    }
+
+    /// Path of the Cargo config file in the root of the workspace.
+    pub fn config_path(&self) -> &Path {
+        self.config.path()
+    }
+
+    /// Cargo config files that can be modified by templates applied to this workspace.
+    pub fn config_locations(&self) -> Vec<ConfigLocation> {
+        config_locations(self.manifest.path())
     }
 
     pub fn existing_profiles(&self) -> Vec<String> {
         self.manifest.get_profiles()
     }
+
+    /// Checks that the profile can inherit from the given parent profile without creating an
+    /// inheritance cycle.
+    pub fn check_inheritance(&self, profile: &Profile, parent: &Profile) -> WizardResult<()> {
+        self.manifest.check_inheritance(profile, parent)
+    }
+
+    /// Audits the built-in profiles and all custom profiles of the workspace.
+    pub fn audit_profiles(&self, options: &WizardOptions) -> WizardResult<Vec<ProfileAudit>> {
+        audit_profiles(&self.manifest, options)
+    }
+
+    /// Minimum supported Rust version of the workspace, if it is specified.
+    pub fn rust_version(&self) -> Option<Version> {
+        self.manifest.rust_version()
+    }
+
+    /// Checks if the toolchain file already pins a nightly toolchain with all components required
+    /// by the template.
+    pub fn has_pinned_nightly_toolchain(&self, template: &Template) -> bool {
+        self.toolchain_file
+            .clone()
+            .pin_nightly(template)
+            .is_ok_and(|file| file.get_text() == self.toolchain_file.get_text())
+    }
 }
 
 /// Workspace that was modified through a template.
 pub struct ModifiedWorkspace {
     manifest: ModificationResult<CargoManifest>,
     config: ModificationResult<CargoConfig>,
+    config_path: PathBuf,
+    toolchain_file: ModificationResult<ToolchainFile>,
+    rustflags_overrides: Vec<RustflagsOverride>,
 }
 
 impl ModifiedWorkspace {
@@ -68,7 +195,22 @@ impl ModifiedWorkspace {
         &self.config
     }
 
-    pub fn write(self) -> anyhow::Result<()> {
+    /// Path of the Cargo config file modified by the template.
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
+    pub fn toolchain_file(&self) -> &ModificationResult<ToolchainFile> {
+        &self.toolchain_file
+    }
+
+    /// Sources of rustflags that override the rustflags written by the template into
+    /// `build.rustflags`, so that Cargo would ignore them.
+    pub fn rustflags_overrides(&self) -> &[RustflagsOverride] {
+        &self.rustflags_overrides
+    }
+
+    pub fn write(self) -> WizardResult<()> {
         match self.manifest {
             ModificationResult::NoChange => {}
             ModificationResult::Modified { new, .. } => {
@@ -81,6 +223,12 @@ impl ModifiedWorkspace {
                 new.write()?;
             }
         }
+        match self.toolchain_file {
+            ModificationResult::NoChange => {}
+            ModificationResult::Modified { new, .. } => {
+                new.write()?;
+            }
+        }
         Ok(())
     }
 }
@@ -91,15 +239,237 @@ pub enum ModificationResult<T> {
     Modified { old: T, new: T },
 }
 
+/// Checks that templates applied together do not set different values of rustflags (e.g.
+/// `-Ctarget-cpu`) or config keys, which are shared by all profiles.
+fn check_shared_settings(
+    templates: &[(&Profile, &Template)],
+    registry: &ItemRegistry,
+) -> WizardResult<()> {
+    // Setting name -> (profile, value)
+    let mut settings: HashMap<String, (&Profile, String)> = HashMap::new();
+    for (profile, template) in templates {
+        let template_settings =
+            template
+                .iter_items()
+                .filter_map(|(id, value)| match registry.location(id)? {
+                    ItemLocation::Rustflag(key) => Some((key.to_string(), rustflag_value(value))),
+                    // Extra rustflags of all templates would end up in all profiles, so they
+                    // have to be the same in all templates that set them
+                    ItemLocation::ExtraRustflags => Some((
+                        "extra rustflags".to_string(),
+                        extra_rustflags(value).join(" "),
+                    )),
+                    ItemLocation::Config { table, key }
+                    | ItemLocation::ConfigList { table, key } => {
+                        Some((format!("{table}.{key}"), value.to_toml_value().to_string()))
+                    }
+                    ItemLocation::Profile(_) => None,
+                });
+        for (setting, value) in template_settings {
+            match settings.get(&setting) {
+                Some((first_profile, first_value)) if *first_value != value => {
+                    return Err(WizardError::SharedSettingConflict {
+                        setting,
+                        first_profile: first_profile.name().to_string(),
+                        first_value: first_value.clone(),
+                        second_profile: profile.name().to_string(),
+                        second_value: value,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    settings.insert(setting, (profile, value));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Parses a Cargo workspace from a Cargo.toml manifest path.
-pub fn parse_workspace(manifest_path: &Path) -> anyhow::Result<CargoWorkspace> {
+pub fn parse_workspace(manifest_path: &Path) -> WizardResult<CargoWorkspace> {
     let manifest = CargoManifest::from_path(manifest_path)?;
     let config = Some(config_path_from_manifest_path(manifest_path))
         .filter(|p| p.exists())
         .map(|path| CargoConfig::from_path(&path))
-        .transpose()
-        .with_context(|| "Cannot load config.toml")?
+        .transpose()?
         .unwrap_or_else(|| CargoConfig::empty_from_manifest(manifest_path));
+    let toolchain_file = Some(toolchain_file_path_from_manifest_path(manifest_path))
+        .filter(|p| p.exists())
+        .map(|path| ToolchainFile::from_path(&path))
+        .transpose()?
+        .unwrap_or_else(|| ToolchainFile::empty_from_manifest(manifest_path));
+
+    Ok(CargoWorkspace {
+        manifest,
+        config,
+        toolchain_file,
+    })
+}
 
-    Ok(CargoWorkspace { manifest, config })
+#[cfg(test)]
+mod tests {
+    use crate::template::TemplateBuilder;
+    use crate::workspace::{ModificationResult, check_shared_settings};
+    use crate::{
+        BuiltinProfile, ItemRegistry, Profile, TemplateItemId, TomlValue, WizardError,
+        WizardOptions, parse_workspace,
+    };
+
+    #[test]
+    fn apply_multiple_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+        std::fs::write(&manifest_path, "").unwrap();
+
+        let dev = TemplateBuilder::new(BuiltinProfile::Dev)
+            .item(TemplateItemId::DebugInfo, TomlValue::int(0))
+            .item(TemplateItemId::Linker, TomlValue::string("lld"))
+            .build();
+        let dist = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(TemplateItemId::Lto, TomlValue::bool(true))
+            .item(
+                TemplateItemId::TargetCpuInstructionSet,
+                TomlValue::string("native"),
+            )
+            .build();
+        let dist_profile = Profile::Custom("dist".to_string());
+        let modified = parse_workspace(&manifest_path)
+            .unwrap()
+            .apply_templates(
+                &[(&Profile::dev(), &dev), (&dist_profile, &dist)],
+                &WizardOptions::default(),
+            )
+            .unwrap();
+
+        let ModificationResult::Modified { new: manifest, .. } = modified.manifest() else {
+            panic!("Manifest was not modified");
+        };
+        insta::assert_snapshot!(manifest.get_text(), @r###"
+        [profile.dev]
+        debug = 0
+
+        [profile.dist]
+        inherits = "release"
+        lto = true
+        "###);
+        let ModificationResult::Modified { new: config, .. } = modified.config() else {
+            panic!("Config was not modified");
+        };
+        insta::assert_snapshot!(config.get_text(), @r###"
+        [build]
+        rustflags = ["-Clink-arg=-fuse-ld=lld", "-Ctarget-cpu=native"]
+        "###);
+    }
+
+    #[test]
+    fn conflicting_rustflags() {
+        let dev = TemplateBuilder::new(BuiltinProfile::Dev)
+            .item(
+                TemplateItemId::TargetCpuInstructionSet,
+                TomlValue::string("x86-64"),
+            )
+            .build();
+        let release = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::TargetCpuInstructionSet,
+                TomlValue::string("native"),
+            )
+            .build();
+        let result = check_shared_settings(
+            &[(&Profile::dev(), &dev), (&Profile::release(), &release)],
+            &ItemRegistry::builtin(),
+        );
+        assert!(matches!(
+            result,
+            Err(WizardError::SharedSettingConflict { .. })
+        ));
+        insta::assert_snapshot!(result.unwrap_err().to_string(), @r###"
+        Templates for profiles dev and release set different values of -Ctarget-cpu (x86-64 and native), which is shared by all profiles
+        "###);
+    }
+
+    #[test]
+    fn conflicting_rustflags_with_equals_sign_in_value() {
+        let dev = TemplateBuilder::new(BuiltinProfile::Dev)
+            .item(TemplateItemId::Linker, TomlValue::string("lld"))
+            .build();
+        let release = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(TemplateItemId::Linker, TomlValue::string("mold"))
+            .build();
+        let result = check_shared_settings(
+            &[(&Profile::dev(), &dev), (&Profile::release(), &release)],
+            &ItemRegistry::builtin(),
+        );
+        insta::assert_snapshot!(result.unwrap_err().to_string(), @r###"
+        Templates for profiles dev and release set different values of -Clink-arg=-fuse-ld (lld and mold), which is shared by all profiles
+        "###);
+    }
+
+    #[test]
+    fn conflicting_extra_rustflags() {
+        let dev = TemplateBuilder::new(BuiltinProfile::Dev)
+            .item(
+                TemplateItemId::ExtraRustflags,
+                TomlValue::string("-Zshare-generics"),
+            )
+            .build();
+        let release = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::ExtraRustflags,
+                TomlValue::string("-Zthreads=8"),
+            )
+            .build();
+        let result = check_shared_settings(
+            &[(&Profile::dev(), &dev), (&Profile::release(), &release)],
+            &ItemRegistry::builtin(),
+        );
+        insta::assert_snapshot!(result.unwrap_err().to_string(), @r###"
+        Templates for profiles dev and release set different values of extra rustflags (-Zshare-generics and -Zthreads=8), which is shared by all profiles
+        "###);
+    }
+
+    #[test]
+    fn same_shared_settings() {
+        let template = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::TargetCpuInstructionSet,
+                TomlValue::string("native"),
+            )
+            .item(
+                TemplateItemId::BuildTarget,
+                TomlValue::string("x86_64-unknown-linux-gnu"),
+            )
+            .build();
+        assert!(
+            check_shared_settings(
+                &[
+                    (&Profile::release(), &template),
+                    (&Profile::Custom("dist".to_string()), &template)
+                ],
+                &ItemRegistry::builtin(),
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn different_rustflags_with_same_prefix() {
+        let dev = TemplateBuilder::new(BuiltinProfile::Dev)
+            .item(TemplateItemId::Linker, TomlValue::string("lld"))
+            .build();
+        let release = TemplateBuilder::new(BuiltinProfile::Release)
+            .item(
+                TemplateItemId::ExtraRustflags,
+                TomlValue::string("-Clink-arg=-s"),
+            )
+            .build();
+        assert!(
+            check_shared_settings(
+                &[(&Profile::dev(), &dev), (&Profile::release(), &release)],
+                &ItemRegistry::builtin(),
+            )
+            .is_ok()
+        );
+    }
 }
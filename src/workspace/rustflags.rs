@@ -0,0 +1,190 @@
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use cargo_metadata::cargo_platform::{Cfg, Platform};
+use toml_edit::DocumentMut;
+
+use crate::toolchain::rustc_command;
+use crate::utils::same_path;
+use crate::workspace::config::{cargo_home_dir, find_config_file};
+
+/// Source of rustflags that takes precedence over `build.rustflags`, which makes Cargo ignore
+/// the flags configured in `.cargo/config.toml`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RustflagsOverride {
+    /// Environment variable, e.g. `RUSTFLAGS`.
+    EnvVar(String),
+    /// `target.<triple>.rustflags` or `target.<cfg>.rustflags` in a Cargo config file.
+    TargetConfig { target: String, path: PathBuf },
+}
+
+impl Display for RustflagsOverride {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RustflagsOverride::EnvVar(name) => write!(f, "the `{name}` environment variable"),
+            RustflagsOverride::TargetConfig { target, path } => {
+                write!(f, "`target.{target}.rustflags` in {}", path.display())
+            }
+        }
+    }
+}
+
+/// Finds sources of rustflags that override `build.rustflags` when Cargo is executed in the
+/// given workspace. This includes environment variables and `target.*.rustflags` in the
+/// config files of the workspace, of its parent directories and of `$CARGO_HOME`.
+///
+/// If `target` is passed, `target.<triple>` sections of other targets are ignored, and
+/// `target.<cfg>` sections are only included if the cfg matches the target.
+/// If the cfg values of the target cannot be determined, all `target.<cfg>` sections are included.
+pub fn find_rustflags_overrides(
+    workspace_root: &Path,
+    target: Option<&str>,
+) -> Vec<RustflagsOverride> {
+    let mut overrides: Vec<_> = [
+        "CARGO_ENCODED_RUSTFLAGS",
+        "RUSTFLAGS",
+        "CARGO_BUILD_RUSTFLAGS",
+    ]
+    .into_iter()
+    .filter(|name| env::var_os(name).is_some())
+    .map(|name| RustflagsOverride::EnvVar(name.to_string()))
+    .collect();
+
+    let mut target_vars: Vec<_> = env::vars_os()
+        .filter_map(|(name, _)| name.into_string().ok())
+        .filter(|name| {
+            let Some(triple) = name
+                .strip_prefix("CARGO_TARGET_")
+                .and_then(|name| name.strip_suffix("_RUSTFLAGS"))
+            else {
+                return false;
+            };
+            target.is_none_or(|target| triple == target.to_uppercase().replace(['-', '.'], "_"))
+        })
+        .collect();
+    target_vars.sort();
+    overrides.extend(target_vars.into_iter().map(RustflagsOverride::EnvVar));
+
+    // Cfg values are only queried from `rustc` when they are needed
+    let mut target_cfg: Option<Option<Vec<Cfg>>> = None;
+    for path in config_files(workspace_root) {
+        let Some(document) = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| text.parse::<DocumentMut>().ok())
+        else {
+            continue;
+        };
+        let Some(targets) = document.get("target").and_then(|t| t.as_table_like()) else {
+            continue;
+        };
+        for (name, table) in targets.iter() {
+            if table.get("rustflags").is_none() {
+                continue;
+            }
+            let matches_target = match name.parse::<Platform>() {
+                Ok(Platform::Cfg(expr)) => target_cfg
+                    .get_or_insert_with(|| get_target_cfg(workspace_root, target))
+                    .as_ref()
+                    .is_none_or(|cfg| expr.matches(cfg)),
+                _ => target.is_none_or(|target| target == name),
+            };
+            if matches_target {
+                overrides.push(RustflagsOverride::TargetConfig {
+                    target: name.to_string(),
+                    path: path.clone(),
+                });
+            }
+        }
+    }
+    overrides
+}
+
+/// Returns the cfg values of the given target (or of the host if no target is passed), as printed
+/// by `rustc --print cfg`.
+fn get_target_cfg(workspace_root: &Path, target: Option<&str>) -> Option<Vec<Cfg>> {
+    let mut cmd = rustc_command(workspace_root);
+    cmd.args(["--print", "cfg"]);
+    if let Some(target) = target {
+        cmd.args(["--target", target]);
+    }
+    let output = cmd.output().ok().filter(|output| output.status.success())?;
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    Some(
+        stdout
+            .lines()
+            .filter_map(|line| line.parse().ok())
+            .collect(),
+    )
+}
+
+/// Returns the paths of Cargo config files that are loaded by Cargo when it is executed in the
+/// given directory.
+fn config_files(directory: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = vec![];
+    let dirs = directory
+        .ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .chain(cargo_home_dir());
+    for dir in dirs {
+        let Some(path) = find_config_file(&dir) else {
+            continue;
+        };
+        if !files.iter().any(|file| same_path(file, &path)) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::workspace::rustflags::{RustflagsOverride, find_rustflags_overrides};
+
+    #[test]
+    fn find_target_rustflags_in_parent_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner = dir.path().join("inner");
+        std::fs::create_dir_all(inner.join(".cargo")).unwrap();
+        std::fs::create_dir_all(dir.path().join(".cargo")).unwrap();
+        std::fs::write(
+            dir.path().join(".cargo").join("config.toml"),
+            r#"
+[target.x86_64-unknown-linux-gnu]
+rustflags = ["-Cfoo"]
+
+[target.aarch64-unknown-linux-gnu]
+rustflags = ["-Cfoo"]
+
+[target.'cfg(unix)']
+rustflags = ["-Cfoo"]
+
+[target.'cfg(windows)']
+rustflags = ["-Cfoo"]
+"#,
+        )
+        .unwrap();
+
+        let config_path = dir.path().join(".cargo").join("config.toml");
+        let overrides: Vec<_> = find_rustflags_overrides(&inner, Some("x86_64-unknown-linux-gnu"))
+            .into_iter()
+            .filter(|o| matches!(o, RustflagsOverride::TargetConfig { .. }))
+            .collect();
+        assert_eq!(
+            overrides,
+            vec![
+                target_override("x86_64-unknown-linux-gnu", config_path.clone()),
+                target_override("cfg(unix)", config_path),
+            ]
+        );
+    }
+
+    fn target_override(target: &str, path: PathBuf) -> RustflagsOverride {
+        RustflagsOverride::TargetConfig {
+            target: target.to_string(),
+            path,
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::path::{Path, PathBuf};
+
+use toml_edit::{Array, DocumentMut, Formatted, Value, table, value};
+
+use crate::{Template, TemplateItemId, TomlValue, WizardError, WizardResult};
+
+/// Toolchain file (`rust-toolchain.toml`) stored in the root of a workspace.
+#[derive(Debug, Clone)]
+pub struct ToolchainFile {
+    path: PathBuf,
+    document: DocumentMut,
+}
+
+impl ToolchainFile {
+    pub fn empty_from_manifest(manifest_path: &Path) -> Self {
+        Self {
+            path: toolchain_file_path_from_manifest_path(manifest_path),
+            document: Default::default(),
+        }
+    }
+
+    pub fn from_path(path: &Path) -> WizardResult<Self> {
+        let text = std::fs::read_to_string(path).map_err(|source| WizardError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let document = match text.parse::<DocumentMut>() {
+            Ok(document) => document,
+            // The legacy `rust-toolchain` file can also contain just the name of the channel
+            Err(_) if is_plain_channel(&text) => {
+                let mut document = DocumentMut::new();
+                document["toolchain"] = table();
+                document["toolchain"]["channel"] = value(text.trim());
+                document
+            }
+            Err(source) => {
+                return Err(WizardError::Parse {
+                    path: path.to_path_buf(),
+                    source,
+                });
+            }
+        };
+
+        Ok(Self {
+            document,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get_text(&self) -> String {
+        self.document.to_string()
+    }
+
+    /// Returns the pinned toolchain channel, if there is any.
+    pub fn channel(&self) -> Option<&str> {
+        self.document
+            .get("toolchain")
+            .and_then(|t| t.get("channel"))
+            .and_then(|c| c.as_str())
+    }
+
+    /// Pins a nightly channel and adds components required by the items of the template.
+    pub fn pin_nightly(mut self, template: &Template) -> WizardResult<Self> {
+        let is_nightly = self
+            .channel()
+            .is_some_and(|channel| channel.starts_with("nightly"));
+
+        let toolchain = self
+            .document
+            .entry("toolchain")
+            .or_insert(table())
+            .as_table_mut()
+            .ok_or_else(|| WizardError::InvalidType {
+                path: self.path.clone(),
+                key: "toolchain".to_string(),
+                expected: "a table",
+            })?;
+        if !is_nightly {
+            let mut new_value = Value::String(Formatted::new("nightly".to_string()));
+            if let Some(existing) = toolchain.get("channel").and_then(|c| c.as_value()) {
+                *new_value.decor_mut() = existing.decor().clone();
+            }
+            toolchain.insert("channel", value(new_value));
+        }
+
+        let required_components = required_components(template);
+        if required_components.is_empty() {
+            return Ok(self);
+        }
+        let components = toolchain
+            .entry("components")
+            .or_insert(value(Array::new()))
+            .as_array_mut()
+            .ok_or_else(|| WizardError::InvalidType {
+                path: self.path.clone(),
+                key: "toolchain.components".to_string(),
+                expected: "an array",
+            })?;
+        for component in required_components {
+            if !components.iter().any(|c| c.as_str() == Some(component)) {
+                components.push(component);
+            }
+        }
+
+        Ok(self)
+    }
+
+    pub fn write(self) -> WizardResult<()> {
+        std::fs::write(&self.path, self.document.to_string()).map_err(|source| WizardError::Write {
+            path: self.path,
+            source,
+        })
+    }
+}
+
+/// Finds the path of the toolchain file in the root of the workspace.
+/// If no toolchain file exists, `rust-toolchain.toml` will be used.
+pub fn toolchain_file_path_from_manifest_path(manifest_path: &Path) -> PathBuf {
+    let root = manifest_path.parent().expect("Manifest path has no parent");
+    let legacy_path = root.join("rust-toolchain");
+    if legacy_path.is_file() {
+        legacy_path
+    } else {
+        root.join("rust-toolchain.toml")
+    }
+}
+
+/// Components that have to be installed to use the items of the template.
+fn required_components(template: &Template) -> Vec<&'static str> {
+    let mut components = vec![];
+    if template.get_item(&TemplateItemId::CodegenBackend) == Some(&TomlValue::string("cranelift")) {
+        components.push("rustc-codegen-cranelift-preview");
+    }
+    if template.get_item(&TemplateItemId::BuildStd).is_some() {
+        components.push("rust-src");
+    }
+    components
+}
+
+fn is_plain_channel(text: &str) -> bool {
+    let text = text.trim();
+    !text.is_empty() && !text.contains(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use toml_edit::DocumentMut;
+
+    use crate::template::TemplateBuilder;
+    use crate::workspace::manifest::BuiltinProfile;
+    use crate::workspace::toolchain_file::ToolchainFile;
+    use crate::{Template, TemplateItemId, TomlValue};
+
+    #[test]
+    fn create_nightly_toolchain() {
+        let file = create_empty_file()
+            .pin_nightly(&create_template(&[]))
+            .unwrap();
+        insta::assert_snapshot!(file.get_text(), @r###"
+        [toolchain]
+        channel = "nightly"
+        "###);
+    }
+
+    #[test]
+    fn add_cranelift_component() {
+        let template = create_template(&[(TemplateItemId::CodegenBackend, "cranelift")]);
+        let file = create_empty_file().pin_nightly(&template).unwrap();
+        insta::assert_snapshot!(file.get_text(), @r###"
+        [toolchain]
+        channel = "nightly"
+        components = ["rustc-codegen-cranelift-preview"]
+        "###);
+    }
+
+    #[test]
+    fn add_rust_src_component() {
+        let template = create_template(&[(TemplateItemId::BuildStd, "std,panic_abort")]);
+        let file = create_empty_file().pin_nightly(&template).unwrap();
+        insta::assert_snapshot!(file.get_text(), @r###"
+        [toolchain]
+        channel = "nightly"
+        components = ["rust-src"]
+        "###);
+    }
+
+    #[test]
+    fn update_existing_toolchain() {
+        let template = create_template(&[(TemplateItemId::CodegenBackend, "cranelift")]);
+        let file = create_file(
+            r#"
+[toolchain]
+channel = "stable" # Foo
+components = ["clippy"]
+"#,
+        );
+        let file = file.pin_nightly(&template).unwrap();
+        insta::assert_snapshot!(file.get_text(), @r###"
+
+        [toolchain]
+        channel = "nightly" # Foo
+        components = ["clippy", "rustc-codegen-cranelift-preview"]
+        "###);
+    }
+
+    #[test]
+    fn keep_dated_nightly() {
+        let file = create_file(
+            r#"
+[toolchain]
+channel = "nightly-2024-03-01"
+"#,
+        );
+        let file = file.pin_nightly(&create_template(&[])).unwrap();
+        insta::assert_snapshot!(file.get_text(), @r###"
+
+        [toolchain]
+        channel = "nightly-2024-03-01"
+        "###);
+    }
+
+    #[test]
+    fn parse_legacy_toolchain_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rust-toolchain");
+        std::fs::write(&path, "stable\n").unwrap();
+
+        let file = ToolchainFile::from_path(&path).unwrap();
+        assert_eq!(file.channel(), Some("stable"));
+    }
+
+    fn create_template(items: &[(TemplateItemId, &str)]) -> Template {
+        let mut builder = TemplateBuilder::new(BuiltinProfile::Dev);
+        for (id, value) in items {
+            builder = builder.item(id.clone(), TomlValue::String(value.to_string()));
+        }
+        builder.build()
+    }
+
+    fn create_file(text: &str) -> ToolchainFile {
+        ToolchainFile {
+            path: Default::default(),
+            document: DocumentMut::from_str(text).unwrap(),
+        }
+    }
+
+    fn create_empty_file() -> ToolchainFile {
+        ToolchainFile {
+            path: Default::default(),
+            document: Default::default(),
+        }
+    }
+}
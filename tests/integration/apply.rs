@@ -76,6 +76,85 @@ members = ["bar"]
     Ok(())
 }
 
+#[test]
+fn apply_redirect_member_manifest_to_root() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    let member_manifest = r#"
+[package]
+name = "bar"
+version = "0.1.0"
+edition = "2021"
+"#;
+    project.file("bar/Cargo.toml", member_manifest);
+    project.file("bar/src/lib.rs", "");
+    project.manifest(
+        r#"
+[workspace]
+members = ["bar"]
+"#,
+    );
+
+    let output = project
+        .cmd(&[
+            "apply",
+            "fast-compile",
+            "dev",
+            "--nightly=off",
+            "--manifest-path",
+            "bar/Cargo.toml",
+        ])
+        .run()?
+        .assert_ok();
+    assert!(output.stderr().contains("is a member of a workspace"));
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+
+    [workspace]
+    members = ["bar"]
+
+    [profile.dev]
+    debug = 0
+    "###);
+    assert_eq!(project.read("bar/Cargo.toml"), member_manifest);
+
+    Ok(())
+}
+
+#[test]
+fn apply_report_ignored_member_profiles() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file(
+        "bar/Cargo.toml",
+        r#"
+[package]
+name = "bar"
+version = "0.1.0"
+edition = "2021"
+
+[profile.release]
+lto = true
+"#,
+    );
+    project.file("bar/src/lib.rs", "");
+    project.manifest(
+        r#"
+[workspace]
+members = ["bar"]
+"#,
+    );
+
+    let output = project
+        .cmd(&["apply", "fast-compile", "dev", "--nightly=off"])
+        .run()?
+        .assert_ok();
+    assert!(
+        output
+            .stderr()
+            .contains("is ignored by Cargo, because it is not the root manifest of the workspace")
+    );
+
+    Ok(())
+}
+
 #[test]
 fn apply_missing_builtin() -> anyhow::Result<()> {
     let project = init_cargo_project()?;
@@ -138,12 +217,7 @@ fn apply_missing_custom() -> anyhow::Result<()> {
 
     [profile.custom1]
     inherits = "dev"
-    opt-level = 0
     debug = 0
-    strip = "none"
-    lto = false
-    codegen-units = 256
-    incremental = true
     "###);
 
     Ok(())
@@ -177,11 +251,6 @@ debug = 1
     [profile.custom1]
     inherits = "dev"
     debug = 0
-    opt-level = 0
-    strip = "none"
-    lto = false
-    codegen-units = 256
-    incremental = true
     "###);
 
     Ok(())
@@ -241,12 +310,8 @@ fn apply_fast_runtime_template() -> anyhow::Result<()> {
 
     [profile.custom]
     inherits = "release"
-    opt-level = 3
-    debug = false
-    strip = "none"
     lto = true
     codegen-units = 1
-    incremental = false
     panic = "abort"
     "###);
 
@@ -272,17 +337,669 @@ fn apply_min_size_template() -> anyhow::Result<()> {
     [profile.custom]
     inherits = "release"
     opt-level = "z"
-    debug = false
     strip = true
     lto = true
     codegen-units = 1
-    incremental = false
     panic = "abort"
     "###);
 
     Ok(())
 }
 
+#[test]
+#[cfg(unix)]
+fn apply_detect_nightly_from_rustc_env() -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut project = init_cargo_project()?.disable_check_on_drop();
+    project.file(
+        "rustc-nightly.sh",
+        r#"#!/bin/sh
+if [ "$1" = "-vV" ]; then
+    echo "rustc 1.95.0-nightly (0123456789 2026-02-01)"
+    echo "binary: rustc"
+    echo "commit-hash: 0123456789"
+    echo "commit-date: 2026-02-01"
+    echo "host: x86_64-unknown-linux-gnu"
+    echo "release: 1.95.0-nightly"
+    echo "LLVM version: 22.1.0"
+else
+    exec rustc "$@"
+fi
+"#,
+    );
+    let rustc = project.path("rustc-nightly.sh");
+    std::fs::set_permissions(&rustc, std::fs::Permissions::from_mode(0o755))?;
+
+    project
+        .cmd(&["apply", "fast-compile", "dev"])
+        .env("RUSTC", rustc.to_str().unwrap())
+        .run()?
+        .assert_ok();
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    cargo-features = ["codegen-backend"]
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.dev]
+    debug = 0
+    codegen-backend = "cranelift"
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn apply_unsupported_by_rust_version() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.manifest(
+        r#"[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+rust-version = "1.58"
+"#,
+    );
+
+    let output = project
+        .cmd(&["apply", "min-size", "release", "--nightly=off"])
+        .run()?;
+    assert!(!output.status.success());
+    assert!(output.stderr().contains(
+        "`Strip symbols` set to `true` requires Cargo 1.59.0 or newer, but the workspace `rust-version` is 1.58.0"
+    ));
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+    rust-version = "1.58"
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn apply_reserved_profile_name() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&["apply", "fast-runtime", "bench", "--nightly=off"])
+        .run()?;
+    assert!(!output.status.success());
+    assert!(output.stderr().contains(
+        "Profile name `bench` is reserved, `cargo bench` uses it and it inherits from `release`. Apply the template to `release` instead."
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn apply_min_size_template_nightly() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    project
+        .cmd(&["apply", "min-size", "release", "--nightly=on"])
+        .run()?
+        .assert_ok();
+    assert!(!project.file_exists(project.config_path()));
+
+    Ok(())
+}
+
+#[test]
+fn apply_write_inherited_values() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    project
+        .cmd(&[
+            "apply",
+            "fast-compile",
+            "custom1",
+            "--nightly=off",
+            "--write-inherited-values",
+        ])
+        .run()?
+        .assert_ok();
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.custom1]
+    inherits = "dev"
+    opt-level = 0
+    debug = 0
+    strip = "none"
+    lto = false
+    codegen-units = 256
+    incremental = true
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn apply_inherit_from_custom_profile() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.dist]
+inherits = "release"
+lto = true
+"#,
+    );
+
+    project
+        .cmd(&[
+            "apply",
+            "fast-runtime",
+            "dist-fast",
+            "--inherits",
+            "dist",
+            "--nightly=off",
+        ])
+        .run()?
+        .assert_ok();
+
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.dist]
+    inherits = "release"
+    lto = true
+
+    [profile.dist-fast]
+    inherits = "dist"
+    codegen-units = 1
+    panic = "abort"
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn apply_inheritance_cycle() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.a]
+inherits = "b"
+
+[profile.b]
+inherits = "release"
+"#,
+    );
+
+    let output = project
+        .cmd(&[
+            "apply",
+            "fast-runtime",
+            "b",
+            "--inherits",
+            "a",
+            "--nightly=off",
+        ])
+        .run()?;
+    assert!(!output.status.success());
+    assert!(
+        output
+            .stderr()
+            .contains("Profile inheritance cycle detected: b -> a -> b")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apply_inherits_builtin_profile() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&[
+            "apply",
+            "fast-runtime",
+            "release",
+            "--inherits",
+            "dev",
+            "--nightly=off",
+        ])
+        .run()?;
+    assert!(!output.status.success());
+    assert!(
+        output
+            .stderr()
+            .contains("Built-in profile release cannot inherit from other profiles")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apply_warn_about_rustflags_env_var() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&["apply", "fast-runtime", "release", "--nightly=off"])
+        .env("RUSTFLAGS", "-Cdebuginfo=1")
+        .run()?
+        .assert_ok();
+    assert!(
+        output
+            .stdout()
+            .contains("Cargo will ignore build.rustflags, because it is overridden by:")
+    );
+    assert!(
+        output
+            .stdout()
+            .contains("- the `RUSTFLAGS` environment variable")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apply_warn_about_target_rustflags_in_cargo_home() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+    let cargo_home = tempfile::tempdir()?;
+    std::fs::write(
+        cargo_home.path().join("config.toml"),
+        r#"
+[target.'cfg(all())']
+rustflags = ["-Cdebuginfo=1"]
+"#,
+    )?;
+
+    let output = project
+        .cmd(&["apply", "fast-runtime", "release", "--nightly=off"])
+        .env("CARGO_HOME", cargo_home.path().to_str().unwrap())
+        .run()?
+        .assert_ok();
+    assert!(
+        output
+            .stdout()
+            .contains("- `target.cfg(all()).rustflags` in")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apply_no_rustflags_override_warning_without_rustflags() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&["apply", "min-size", "release", "--nightly=off"])
+        .env("RUSTFLAGS", "-Cdebuginfo=1")
+        .run()?
+        .assert_ok();
+    assert!(
+        !output
+            .stdout()
+            .contains("Cargo will ignore build.rustflags")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apply_warn_about_cargo_build_rustflags() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&["apply", "fast-runtime", "release", "--nightly=off"])
+        .env("CARGO_BUILD_RUSTFLAGS", "-Cdebuginfo=1")
+        .run()?
+        .assert_ok();
+    assert!(
+        output
+            .stdout()
+            .contains("- the `CARGO_BUILD_RUSTFLAGS` environment variable")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apply_custom_config_path() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+    let config_path = project.path("custom-config.toml");
+
+    project
+        .cmd(&[
+            "apply",
+            "fast-runtime",
+            "release",
+            "--nightly=off",
+            "--config-path",
+            config_path.to_str().unwrap(),
+        ])
+        .run()?
+        .assert_ok();
+
+    insta::assert_snapshot!(std::fs::read_to_string(&config_path)?, @r###"
+    [build]
+    rustflags = ["-Ctarget-cpu=native"]
+    "###);
+    assert!(!project.config_path().exists());
+
+    Ok(())
+}
+
+#[test]
+fn apply_legacy_config_file() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file(
+        ".cargo/config",
+        r#"
+[build]
+rustflags = ["-Cdebuginfo=1"]
+"#,
+    );
+
+    project
+        .cmd(&["apply", "fast-runtime", "release", "--nightly=off"])
+        .run()?
+        .assert_ok();
+
+    insta::assert_snapshot!(project.read(".cargo/config"), @r###"
+
+    [build]
+    rustflags = ["-Cdebuginfo=1", "-Ctarget-cpu=native"]
+    "###);
+    assert!(!project.config_path().exists());
+
+    Ok(())
+}
+
+#[test]
+fn apply_normalize_string_rustflags() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.config(
+        r#"
+[build]
+rustflags = "-Ctarget-cpu=x86-64 -Cdebuginfo=1"
+"#,
+    );
+
+    project
+        .cmd(&[
+            "apply",
+            "fast-runtime",
+            "release",
+            "--nightly=off",
+            "--normalize-rustflags",
+        ])
+        .run()?
+        .assert_ok();
+
+    insta::assert_snapshot!(project.read_config(), @r###"
+
+    [build]
+    rustflags = ["-Ctarget-cpu=native", "-Cdebuginfo=1"]
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn apply_emit_env() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+    let manifest = project.read_manifest();
+
+    let output = project
+        .cmd(&[
+            "apply",
+            "fast-runtime",
+            "dist",
+            "--nightly=off",
+            "--emit",
+            "env",
+        ])
+        .run()?
+        .assert_ok();
+
+    insta::assert_snapshot!(output.stdout(), @r###"
+    export CARGO_PROFILE_DIST_INHERITS='release'
+    export CARGO_PROFILE_DIST_OPT_LEVEL='3'
+    export CARGO_PROFILE_DIST_DEBUG='false'
+    export CARGO_PROFILE_DIST_STRIP='none'
+    export CARGO_PROFILE_DIST_LTO='true'
+    export CARGO_PROFILE_DIST_CODEGEN_UNITS='1'
+    export CARGO_PROFILE_DIST_INCREMENTAL='false'
+    export CARGO_PROFILE_DIST_PANIC='abort'
+    export CARGO_BUILD_RUSTFLAGS='-Ctarget-cpu=native'
+    "###);
+    assert_eq!(project.read_manifest(), manifest);
+    assert!(!project.config_path().exists());
+
+    Ok(())
+}
+
+#[test]
+fn apply_emit_env_github_actions() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&[
+            "apply",
+            "min-size",
+            "release",
+            "--nightly=off",
+            "--emit",
+            "env",
+            "--env-format",
+            "github-actions",
+        ])
+        .run()?
+        .assert_ok();
+
+    insta::assert_snapshot!(output.stdout(), @r###"
+    env:
+      CARGO_PROFILE_RELEASE_OPT_LEVEL: "z"
+      CARGO_PROFILE_RELEASE_DEBUG: "false"
+      CARGO_PROFILE_RELEASE_STRIP: "true"
+      CARGO_PROFILE_RELEASE_LTO: "true"
+      CARGO_PROFILE_RELEASE_CODEGEN_UNITS: "1"
+      CARGO_PROFILE_RELEASE_INCREMENTAL: "false"
+      CARGO_PROFILE_RELEASE_PANIC: "abort"
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn apply_multiple_templates() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&[
+            "apply",
+            "fast-compile=dev",
+            "fast-runtime=release",
+            "min-size=dist",
+            "--nightly=off",
+        ])
+        .run()?
+        .assert_ok();
+
+    assert!(
+        output
+            .stdout()
+            .contains("Template FastCompile applied to profile dev")
+    );
+    assert!(
+        output
+            .stdout()
+            .contains("Template FastRuntime applied to profile release")
+    );
+    assert!(
+        output
+            .stdout()
+            .contains("Template MinSize applied to profile dist")
+    );
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.dev]
+    debug = 0
+
+    [profile.release]
+    lto = true
+    codegen-units = 1
+    panic = "abort"
+
+    [profile.dist]
+    inherits = "release"
+    opt-level = "z"
+    strip = true
+    "###);
+    // The rustflags of `fast-compile` depend on the host
+    assert!(project.read_config().contains("-Ctarget-cpu=native"));
+
+    Ok(())
+}
+
+#[test]
+fn apply_invalid_target() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&["apply", "fast-runtime=release", "dist", "--nightly=off"])
+        .run()?;
+    assert!(!output.status.success());
+    assert!(output.stderr().contains(
+        "Invalid target `dist`, expected `<template> <profile>` or `<template>=<profile>` pairs"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn apply_invalid_template_in_pair() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&["apply", "fast-runtime=release", "foo=dist", "--nightly=off"])
+        .run()?;
+    assert!(!output.status.success());
+    assert!(
+        output
+            .stderr()
+            .contains("[possible values: fast-compile, fast-runtime, min-size, profiling]")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apply_env_format_without_emit_env() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+    let manifest = project.read_manifest();
+
+    let output = project
+        .cmd(&[
+            "apply",
+            "fast-runtime",
+            "release",
+            "--env-format",
+            "dotenv",
+            "--nightly=off",
+        ])
+        .run()?;
+    assert!(!output.status.success());
+    assert!(
+        output
+            .stderr()
+            .contains("`--env-format` requires `--emit env`")
+    );
+    assert_eq!(project.read_manifest(), manifest);
+
+    Ok(())
+}
+
+#[test]
+fn apply_same_profile_twice() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+    let manifest = project.read_manifest();
+
+    let output = project
+        .cmd(&[
+            "apply",
+            "fast-runtime=release",
+            "min-size=release",
+            "--nightly=off",
+        ])
+        .run()?;
+    assert!(!output.status.success());
+    assert!(
+        output
+            .stderr()
+            .contains("Profile release is specified multiple times")
+    );
+    assert_eq!(project.read_manifest(), manifest);
+
+    Ok(())
+}
+
+#[test]
+// `split-debuginfo` depends on the host
+#[cfg(target_os = "linux")]
+fn apply_profiling_template() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&["apply", "profiling", "profiling", "--nightly=off"])
+        .run()?
+        .assert_ok();
+    assert!(
+        output
+            .stdout()
+            .contains("samply record target/profiling/<binary>")
+    );
+    assert!(output.stdout().contains(
+        "Frame pointers (-Cforce-frame-pointers=yes) are forced in all profiles, because rustflags are shared by all profiles."
+    ));
+
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.profiling]
+    inherits = "release"
+    debug = "line-tables-only"
+    strip = false
+    split-debuginfo = "off"
+    "###);
+    insta::assert_snapshot!(project.read_config(), @r###"
+    [build]
+    rustflags = ["-Cforce-frame-pointers=yes"]
+    "###);
+
+    Ok(())
+}
+
 fn apply(project: &CargoProject, profile: &str, template: &str) -> anyhow::Result<()> {
     project
         .cmd(&["apply", template, profile, "--nightly=off"])
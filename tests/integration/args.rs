@@ -0,0 +1,67 @@
+use crate::utils::{OutputExt, init_cargo_project};
+
+#[test]
+fn args_print_invocation() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+    let manifest = project.read_manifest();
+
+    let output = project
+        .cmd(&["args", "fast-runtime", "dist", "--nightly=off"])
+        .run()?
+        .assert_ok();
+
+    insta::assert_snapshot!(output.stdout(), @r###"
+    cargo build --profile dist --config 'profile.dist.inherits="release"' --config 'profile.dist.opt-level=3' --config 'profile.dist.debug=false' --config 'profile.dist.strip="none"' --config 'profile.dist.lto=true' --config 'profile.dist.codegen-units=1' --config 'profile.dist.incremental=false' --config 'profile.dist.panic="abort"' --config 'build.rustflags=["-Ctarget-cpu=native"]'
+    "###);
+    assert_eq!(project.read_manifest(), manifest);
+    assert!(!project.config_path().exists());
+
+    Ok(())
+}
+
+#[test]
+fn args_custom_command() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&[
+            "args",
+            "min-size",
+            "release",
+            "--nightly=off",
+            "--command",
+            "check",
+        ])
+        .run()?
+        .assert_ok();
+
+    insta::assert_snapshot!(output.stdout(), @r###"
+    cargo check --profile release --config 'profile.release.opt-level="z"' --config 'profile.release.debug=false' --config 'profile.release.strip=true' --config 'profile.release.lto=true' --config 'profile.release.codegen-units=1' --config 'profile.release.incremental=false' --config 'profile.release.panic="abort"'
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn args_run_cargo() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let output = project
+        .cmd(&[
+            "args",
+            "min-size",
+            "dist",
+            "--nightly=off",
+            "--command",
+            "check",
+            "--run",
+        ])
+        .run()?
+        .assert_ok();
+
+    assert!(output.stderr().contains("Finished `dist` profile"));
+    assert!(project.path("target/dist").is_dir());
+    assert!(!project.config_path().exists());
+
+    Ok(())
+}
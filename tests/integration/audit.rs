@@ -0,0 +1,48 @@
+use crate::utils::{OutputExt, init_cargo_project};
+
+#[test]
+fn audit_profiles() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.release]
+incremental = true
+
+[profile.dist]
+inherits = "release"
+lto = true
+codegen-units = 1
+"#,
+    );
+
+    let output = project.cmd(&["audit", "--nightly=off"]).run()?.assert_ok();
+    insta::assert_snapshot!(output.stdout(), @r###"
+    Profile dev
+      Closest template: FastCompile (5/6 settings match)
+      Suggested changes:
+        - Debug info: `0`
+
+    Profile release
+      Closest template: FastRuntime (3/7 settings match)
+      ⚠️  `incremental = true` is combined with `opt-level = 3`, which limits the optimizations of the compiler
+      Suggested changes:
+        - Incremental compilation: `false` (Disable)
+        - Link-time optimizations: `true` (Fat LTO)
+        - Number of codegen units (CGUs): `1` (1 CGU)
+        - Panic handling mechanism: `"abort"` (Abort)
+
+    Profile dist
+      Closest template: FastRuntime (5/7 settings match)
+      ⚠️  `incremental = true` is combined with `opt-level = 3`, which limits the optimizations of the compiler
+      Suggested changes:
+        - Incremental compilation: `false` (Disable)
+        - Panic handling mechanism: `"abort"` (Abort)
+    "###);
+
+    Ok(())
+}
@@ -45,7 +45,7 @@ fn dialog_skip_default_value_for_dev() -> anyhow::Result<()> {
 }
 
 #[test]
-fn dialog_do_not_skip_default_value_for_custom_profile() -> anyhow::Result<()> {
+fn dialog_skip_inherited_value_for_custom_profile() -> anyhow::Result<()> {
     let project = init_cargo_project()?;
     DialogBuilder::default()
         .template("FastCompile")
@@ -60,12 +60,7 @@ fn dialog_do_not_skip_default_value_for_custom_profile() -> anyhow::Result<()> {
 
     [profile.foo]
     inherits = "dev"
-    opt-level = 0
     debug = 0
-    strip = "none"
-    lto = false
-    codegen-units = 256
-    incremental = true
     "###);
 
     Ok(())
@@ -201,11 +196,6 @@ debug = 1
     [profile.custom1]
     inherits = "dev"
     debug = 0
-    opt-level = 0
-    strip = "none"
-    lto = false
-    codegen-units = 256
-    incremental = true
     "###);
 
     Ok(())
@@ -228,12 +218,7 @@ fn dialog_fast_compile_to_new_profile() -> anyhow::Result<()> {
 
     [profile.custom1]
     inherits = "dev"
-    opt-level = 0
     debug = 0
-    strip = "none"
-    lto = false
-    codegen-units = 256
-    incremental = true
     "###);
 
     Ok(())
@@ -249,6 +234,14 @@ fn dialog_empty_profile_name() -> anyhow::Result<()> {
     terminal.expect("Profile name must not be empty")
 }
 
+#[test]
+fn dialog_show_detected_toolchain() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let mut terminal = DialogBuilder::default().start(&project)?;
+    terminal.expect("Detected toolchain:")
+}
+
 #[test]
 fn dialog_invalid_profile_name() -> anyhow::Result<()> {
     let project = init_cargo_project()?;
@@ -259,6 +252,26 @@ fn dialog_invalid_profile_name() -> anyhow::Result<()> {
     terminal.expect("Profile name may contain only letters, numbers, underscore and hyphen")
 }
 
+#[test]
+fn dialog_reserved_profile_name() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let mut terminal = DialogBuilder::default().start(&project)?;
+    terminal.select_line("<Create a new profile>")?;
+    terminal.line("cargo-foo")?;
+    terminal.expect("Profile name `cargo-foo` is reserved by Cargo")
+}
+
+#[test]
+fn dialog_builtin_alias_profile_name() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let mut terminal = DialogBuilder::default().start(&project)?;
+    terminal.select_line("<Create a new profile>")?;
+    terminal.line("bench")?;
+    terminal.expect("Profile name `bench` is reserved")
+}
+
 #[test]
 fn dialog_create_config() -> anyhow::Result<()> {
     let project = init_cargo_project()?;
@@ -273,6 +286,92 @@ fn dialog_create_config() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn dialog_extra_rustflags() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    DialogBuilder::default()
+        .template("FastRuntime")
+        .profile_release()
+        .customize_item(
+            "Extra rustflags",
+            CustomValue::List(vec![
+                "-Cforce-frame-pointers=yes".to_string(),
+                "-Csymbol-mangling-version=v0".to_string(),
+            ]),
+        )
+        .run(&project)?;
+
+    insta::assert_snapshot!(project.read_config(), @r###"
+    [build]
+    rustflags = ["-Ctarget-cpu=native", "-Cforce-frame-pointers=yes", "-Csymbol-mangling-version=v0"]
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn dialog_add_split_rustflags() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    DialogBuilder::default()
+        .template("FastRuntime")
+        .profile_release()
+        .customize_item(
+            "Extra rustflags",
+            CustomValue::List(vec![
+                "-C opt-level=1".to_string(),
+                "-C debuginfo=2".to_string(),
+            ]),
+        )
+        .run(&project)?;
+
+    insta::assert_snapshot!(project.read_config(), @r###"
+    [build]
+    rustflags = ["-Ctarget-cpu=native", "-C", "opt-level=1", "-C", "debuginfo=2"]
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn dialog_edit_rustflags_with_whitespace() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    DialogBuilder::default()
+        .template("FastRuntime")
+        .profile_release()
+        .customize_item(
+            "Extra rustflags",
+            CustomValue::List(vec![r#"["-Clink-args=-Wl,-z -Wl,now"]"#.to_string()]),
+        )
+        .customize_item(
+            "Extra rustflags",
+            CustomValue::List(vec!["-Cforce-frame-pointers=yes".to_string()]),
+        )
+        .run(&project)?;
+
+    insta::assert_snapshot!(project.read_config(), @r###"
+    [build]
+    rustflags = ["-Ctarget-cpu=native", "-Clink-args=-Wl,-z -Wl,now", "-Cforce-frame-pointers=yes"]
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn dialog_warn_about_rustflags_override() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    DialogBuilder::default()
+        .template("FastRuntime")
+        .profile_release()
+        .env("CARGO_ENCODED_RUSTFLAGS", "")
+        .with_final_check("Cargo will ignore build.rustflags")
+        .with_final_check("the `CARGO_ENCODED_RUSTFLAGS` environment variable")
+        .run(&project)
+}
+
 #[test]
 fn dialog_append_to_config() -> anyhow::Result<()> {
     let mut project = init_cargo_project()?;
@@ -319,6 +418,7 @@ fn dialog_codegen_backend_add_cargo_features() -> anyhow::Result<()> {
 
     DialogBuilder::default()
         .customize_item("Codegen backend", "Cranelift")
+        .pin_toolchain(false)
         .run(&project)?;
 
     insta::assert_snapshot!(project.read_manifest(), @r###"
@@ -351,6 +451,7 @@ edition = "2021"
 
     DialogBuilder::default()
         .customize_item("Codegen backend", "Cranelift")
+        .pin_toolchain(false)
         .run(&project)?;
 
     insta::assert_snapshot!(project.read_manifest(), @r###"
@@ -376,6 +477,7 @@ fn dialog_check_nightly_output() -> anyhow::Result<()> {
     DialogBuilder::default()
         .profile_release()
         .customize_item("Codegen backend", "Cranelift")
+        .pin_toolchain(false)
         .with_final_check("cargo +nightly <cmd>")
         .with_final_check("You will have to use a nightly compiler")
         .run(&project)?;
@@ -383,6 +485,27 @@ fn dialog_check_nightly_output() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn dialog_pin_nightly_toolchain() -> anyhow::Result<()> {
+    let project = init_cargo_project()?.disable_check_on_drop();
+
+    DialogBuilder::default()
+        .profile_release()
+        .customize_item("Codegen backend", "Cranelift")
+        .pin_toolchain(true)
+        .with_final_check("cargo <cmd> --release")
+        .with_final_check("Nightly toolchain pinned in rust-toolchain.toml")
+        .run(&project)?;
+
+    insta::assert_snapshot!(project.read("rust-toolchain.toml"), @r###"
+    [toolchain]
+    channel = "nightly"
+    components = ["rustc-codegen-cranelift-preview"]
+    "###);
+
+    Ok(())
+}
+
 #[test]
 fn dialog_codegen_backend_nightly_mark() -> anyhow::Result<()> {
     let project = init_cargo_project()?.disable_check_on_drop();
@@ -390,6 +513,7 @@ fn dialog_codegen_backend_nightly_mark() -> anyhow::Result<()> {
     DialogBuilder::default()
         .profile_release()
         .customize_item("Codegen backend *", "Cranelift")
+        .pin_toolchain(false)
         .run(&project)?;
 
     Ok(())
@@ -405,6 +529,7 @@ fn dialog_fast_compile_nightly() -> anyhow::Result<()> {
             "Number of frontend threads",
             CustomValue::Custom("4".to_string()),
         )
+        .pin_toolchain(false)
         .run(&project)?;
 
     insta::assert_snapshot!(project.read_manifest(), @r###"
@@ -450,8 +575,231 @@ fn dialog_unset_item() -> anyhow::Result<()> {
     edition = "2021"
 
     [profile.dev]
+    opt-level = 3
+    debug = false
     lto = true
     codegen-units = 1
+    incremental = false
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn dialog_inherit_from_custom_profile() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.dist]
+inherits = "release"
+lto = true
+"#,
+    );
+
+    DialogBuilder::default()
+        .template("FastRuntime")
+        .create_profile("dist-fast")
+        .customize_item("Inherits from", "dist")
+        .run(&project)?;
+
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.dist]
+    inherits = "release"
+    lto = true
+
+    [profile.dist-fast]
+    inherits = "dist"
+    codegen-units = 1
+    panic = "abort"
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn dialog_select_config_file_in_parent_directory() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    let parent_config = project.dir.parent().unwrap().join(".cargo/config.toml");
+    project.file(
+        &parent_config,
+        r#"
+[build]
+rustflags = ["-Cdebuginfo=1"]
+"#,
+    );
+
+    DialogBuilder::default()
+        .template("FastRuntime")
+        .profile_release()
+        .customize_item("Config file", "Parent directory")
+        .run(&project)?;
+
+    insta::assert_snapshot!(std::fs::read_to_string(&parent_config)?, @r###"
+
+    [build]
+    rustflags = ["-Cdebuginfo=1", "-Ctarget-cpu=native"]
+    "###);
+    assert!(!project.config_path().exists());
+
+    Ok(())
+}
+
+#[test]
+fn dialog_remove_unset_items() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.release]
+lto = "thin"
+"#,
+    );
+    project.config(
+        r#"
+[build]
+rustflags = ["-Ctarget-cpu=native", "-Cdebuginfo=1"]
+"#,
+    );
+
+    DialogBuilder::default()
+        .template("FastRuntime")
+        .profile_release()
+        .customize_item("Link-time optimizations", "<Unset value>")
+        .customize_item("Target CPU instruction set", "<Unset value>")
+        .run(&project)?;
+
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.release]
+    codegen-units = 1
+    panic = "abort"
+    "###);
+    insta::assert_snapshot!(project.read_config(), @r###"
+    [build]
+    rustflags = ["-Cdebuginfo=1"]
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn init_default_profiles() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    let mut terminal = project.cmd(&["init", "--nightly=off"]).start_terminal()?;
+    terminal.expect("Is this a binary that you ship to users?")?;
+    terminal.line("y")?;
+    terminal.expect("Do you care about the size of the shipped binary?")?;
+    terminal.line("n")?;
+    terminal.expect("Where are your release builds made?")?;
+    terminal.select_line("Locally")?;
+    terminal.expect("Do you want to profile optimized builds?")?;
+    terminal.line("n")?;
+    terminal.expect("Do you use a nightly compiler?")?;
+    terminal.line("n")?;
+    terminal.expect("Do you want to apply the above diff")?;
+    terminal.line("y")?;
+    terminal.expect("Template FastCompile applied to profile dev")?;
+    terminal.expect("Template FastRuntime applied to profile release")?;
+    terminal.wait()?;
+
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.dev]
+    debug = 0
+
+    [profile.release]
+    lto = true
+    codegen-units = 1
+    panic = "abort"
+    "###);
+    assert!(project.read_config().contains("-Ctarget-cpu=native"));
+
+    Ok(())
+}
+
+#[test]
+fn init_all_profiles() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.config(
+        r#"
+[build]
+rustflags = ["-Ctarget-cpu=x86-64-v2"]
+"#,
+    );
+
+    let mut terminal = project.cmd(&["init", "--nightly=off"]).start_terminal()?;
+    terminal.expect("Is this a binary that you ship to users?")?;
+    terminal.line("y")?;
+    terminal.expect("Do you care about the size of the shipped binary?")?;
+    terminal.line("y")?;
+    terminal.expect("Where are your release builds made?")?;
+    terminal.select_line("In CI")?;
+    terminal.expect("Do you want to profile optimized builds?")?;
+    terminal.line("y")?;
+    terminal.expect("Do you use a nightly compiler?")?;
+    terminal.line("n")?;
+    terminal.expect("Do you want to apply the above diff")?;
+    terminal.line("y")?;
+    terminal.expect("Template MinSize applied to profile min-size")?;
+    terminal.expect("Template Profiling applied to profile profiling")?;
+    terminal.expect(
+        "Frame pointers (-Cforce-frame-pointers=yes) are not forced in the profiling profile",
+    )?;
+    terminal.wait()?;
+
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.dev]
+    debug = 0
+
+    [profile.release]
+    lto = true
+    codegen-units = 1
+    panic = "abort"
+
+    [profile.min-size]
+    inherits = "release"
+    opt-level = "z"
+    strip = true
+
+    [profile.profiling]
+    inherits = "release"
+    debug = "line-tables-only"
+    strip = false
+    split-debuginfo = "off"
+    "###);
+    // Portable release builds must not use `-Ctarget-cpu=native`, but the existing target CPU
+    // is kept
+    insta::assert_snapshot!(project.read_config(), @r###"
+    [build]
+    rustflags = ["-Ctarget-cpu=x86-64-v2"]
     "###);
 
     Ok(())
@@ -460,6 +808,7 @@ fn dialog_unset_item() -> anyhow::Result<()> {
 enum CustomValue {
     Constant(String),
     Custom(String),
+    List(Vec<String>),
 }
 
 impl<'a> From<&'a str> for CustomValue {
@@ -474,8 +823,10 @@ struct DialogBuilder {
     template: String,
     nightly: bool,
     accept_diff: bool,
+    pin_toolchain: Option<bool>,
     customized_items: Vec<(String, CustomValue)>,
     final_checks: Vec<String>,
+    env: Vec<(String, String)>,
 }
 
 impl Default for DialogBuilder {
@@ -486,8 +837,10 @@ impl Default for DialogBuilder {
             template: "FastCompile".to_string(),
             nightly: false,
             accept_diff: true,
+            pin_toolchain: None,
             customized_items: vec![],
             final_checks: vec![],
+            env: vec![],
         }
     }
 }
@@ -523,6 +876,13 @@ impl DialogBuilder {
         self
     }
 
+    /// Answer the prompt for pinning a nightly toolchain, which appears when the template
+    /// contains nightly items.
+    fn pin_toolchain(mut self, value: bool) -> Self {
+        self.pin_toolchain = Some(value);
+        self
+    }
+
     fn customize_item<V: Into<CustomValue>>(mut self, name: &str, value: V) -> Self {
         self.customized_items.push((name.to_string(), value.into()));
         self
@@ -533,14 +893,21 @@ impl DialogBuilder {
         self
     }
 
+    fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
     fn start(&self, project: &CargoProject) -> anyhow::Result<Terminal> {
         let nightly = match self.nightly {
             true => "on",
             false => "off",
         };
-        let terminal = project
-            .cmd(&[&format!("--nightly={nightly}")])
-            .start_terminal()?;
+        let mut cmd = project.cmd(&[&format!("--nightly={nightly}")]);
+        for (key, value) in &self.env {
+            cmd = cmd.env(key, value);
+        }
+        let terminal = cmd.start_terminal()?;
         Ok(terminal)
     }
 
@@ -570,10 +937,23 @@ impl DialogBuilder {
                     terminal.select_line("Custom value")?;
                     terminal.line(&value)?;
                 }
+                // Add entries to a list
+                CustomValue::List(entries) => {
+                    for entry in entries {
+                        terminal.select_line("<Add entry>")?;
+                        terminal.expect("Enter entry")?;
+                        terminal.line(&entry)?;
+                    }
+                    terminal.select_line("<Confirm>")?;
+                }
             }
         }
         // Confirm template
         terminal.key_enter()?;
+        if let Some(pin) = self.pin_toolchain {
+            terminal.expect("Do you want to pin a nightly toolchain")?;
+            terminal.line(if pin { "y" } else { "n" })?;
+        }
         terminal.expect("Do you want to apply the above diff")?;
 
         let profile_name = self.created_profile.unwrap_or(self.profile);
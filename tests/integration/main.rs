@@ -1,4 +1,6 @@
 mod apply;
+mod args;
+mod audit;
 #[cfg(target_os = "linux")]
 mod dialog;
 mod utils;
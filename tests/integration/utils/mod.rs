@@ -97,6 +97,7 @@ impl Drop for CargoProject {
 pub struct Cmd {
     arguments: Vec<String>,
     cwd: Option<PathBuf>,
+    env: Vec<(String, String)>,
     stdin: Vec<u8>,
 }
 
@@ -141,6 +142,12 @@ impl Cmd {
         .expect("Could not construct PATH for integration test command");
 
         command.env("PATH", path);
+        // Make sure that the environment of the tests does not override rustflags
+        command.env_remove("RUSTFLAGS");
+        command.env_remove("CARGO_ENCODED_RUSTFLAGS");
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
         command
     }
 
@@ -149,6 +156,11 @@ impl Cmd {
         self
     }
 
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
     pub fn cwd(self, cwd: &Path) -> Self {
         Self {
             cwd: Some(cwd.to_path_buf()),
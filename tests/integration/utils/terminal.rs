@@ -29,7 +29,7 @@ impl Terminal {
 
     /// Find a line that begings by `> {prefix}` by going through a list using the down arrow key.
     pub fn select_line(&mut self, prefix: &str) -> anyhow::Result<()> {
-        let max_tries = 20;
+        let max_tries = 30;
         for _ in 0..max_tries {
             if self
                 .session